@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use spacer::camera::{AdaptiveSampling, Camera, CameraParams};
+use spacer::color::Color;
+use spacer::image::Image;
+use spacer::integrator::PathIntegrator;
+use spacer::light::{SkyLight, SkyParams};
+use spacer::material::Material;
+use spacer::math::{Transform, Vec3, vec3};
+use spacer::primitives::{BvhNode, HittableList, Sphere};
+use spacer::renderer::{MtRenderer, Renderer};
+use spacer::scene::Scene;
+
+const CANVAS_WIDTH: u32 = 640;
+const CANVAS_HEIGHT: u32 = 360;
+
+fn main() {
+    let camera_params = CameraParams {
+        image_width: CANVAS_WIDTH,
+        image_height: CANVAS_HEIGHT,
+        fov: f32::to_radians(40.0),
+        samples_per_pixel: 16,
+        ..CameraParams::default()
+    };
+    let mut camera = Camera::new(camera_params);
+    camera.transform = Transform::look_at(vec3(0.0, 1.5, 8.0), vec3(0.0, 1.0, 0.0), Vec3::Y);
+    // The flat sky converges after the first batch, the objects get up to 128 samples
+    camera.adaptive = Some(AdaptiveSampling::new(128, 0.05));
+
+    let mut world = HittableList::default();
+    world.add(Arc::new(Sphere {
+        center: vec3(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Material::lambertian(Color::new(0.5, 0.5, 0.5))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(-1.1, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::lambertian(Color::new(0.8, 0.3, 0.3))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(1.1, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::metalic(Color::new(0.8, 0.8, 0.8), 0.3)),
+    }));
+
+    let mut scene = Scene::new(Arc::new(BvhNode::new(&mut world)));
+    let sky = SkyLight::new(SkyParams::default());
+    scene.add_light(Arc::new(sky.sun()));
+    scene.add_light(Arc::new(sky));
+
+    let integrator = PathIntegrator::new(8);
+    let renderer = MtRenderer::default();
+
+    let mut image = Image::new(CANVAS_WIDTH, CANVAS_HEIGHT);
+    let render_timer = Instant::now();
+    renderer.render(&camera, &mut image, |ray, sampler| {
+        integrator.radiance(&scene, ray, sampler)
+    });
+    println!("Frame rendered in {}ms", render_timer.elapsed().as_millis());
+    image
+        .save_as_ppm("output/adaptive.ppm")
+        .expect("Saving image");
+
+    // Renders the same samples again to show where they were spent
+    if let Some(adaptive) = camera.adaptive.as_mut() {
+        adaptive.show_sample_counts = true;
+    }
+    let mut heatmap = Image::new(CANVAS_WIDTH, CANVAS_HEIGHT);
+    renderer.render(&camera, &mut heatmap, |ray, sampler| {
+        integrator.radiance(&scene, ray, sampler)
+    });
+    heatmap
+        .save_as_ppm("output/adaptive_samples.ppm")
+        .expect("Saving image");
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use spacer::camera::{Camera, CameraParams};
+use spacer::color::Color;
+use spacer::image::Image;
+use spacer::integrator::PathIntegrator;
+use spacer::light::{DirectionalLight, PointLight, SpotLight};
+use spacer::material::Material;
+use spacer::math::{Transform, Vec3, vec3};
+use spacer::primitives::{BvhNode, HittableList, Sphere};
+use spacer::renderer::{MtRenderer, Renderer};
+use spacer::scene::Scene;
+
+const CANVAS_WIDTH: u32 = 800;
+const CANVAS_HEIGHT: u32 = 450;
+
+fn main() {
+    let mut image = Image::new(CANVAS_WIDTH, CANVAS_HEIGHT);
+
+    let camera_params = CameraParams {
+        image_width: CANVAS_WIDTH,
+        image_height: CANVAS_HEIGHT,
+        fov: f32::to_radians(40.0),
+        samples_per_pixel: 16,
+        ..CameraParams::default()
+    };
+    let mut camera = Camera::new(camera_params);
+    camera.transform = Transform::look_at(vec3(0.0, 2.0, 8.0), vec3(0.0, 0.5, 0.0), Vec3::Y);
+
+    let mut world = HittableList::default();
+    world.add(Arc::new(Sphere {
+        center: vec3(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Material::lambertian(Color::new(0.5, 0.5, 0.5))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(-2.2, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::lambertian(Color::new(0.8, 0.3, 0.3))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::metalic(Color::new(0.8, 0.8, 0.8), 0.1)),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(2.2, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::oren_nayar(Color::new(0.3, 0.3, 0.8), 0.5)),
+    }));
+
+    let mut scene = Scene::new(Arc::new(BvhNode::new(&mut world)));
+    scene.background = Color::new(0.02, 0.02, 0.03);
+    scene.add_light(Arc::new(PointLight {
+        position: vec3(-3.0, 4.0, 3.0),
+        intensity: Color::new(20.0, 16.0, 12.0),
+    }));
+    scene.add_light(Arc::new(SpotLight {
+        position: vec3(3.0, 5.0, 2.0),
+        direction: vec3(-0.5, -1.0, -0.4),
+        intensity: Color::new(40.0, 40.0, 50.0),
+        cone_angle: f32::to_radians(30.0),
+        falloff_angle: f32::to_radians(20.0),
+    }));
+    scene.add_light(Arc::new(DirectionalLight {
+        direction: vec3(1.0, -1.0, -1.0),
+        irradiance: Color::new(0.3, 0.3, 0.3),
+    }));
+
+    let integrator = PathIntegrator::new(8);
+    let render_timer = Instant::now();
+    let renderer = MtRenderer::default();
+    renderer.render(&camera, &mut image, |ray, sampler| {
+        integrator.radiance(&scene, ray, sampler)
+    });
+
+    let frame_time = render_timer.elapsed();
+    println!("Frame rendered in {}ms", frame_time.as_millis());
+
+    image
+        .save_as_ppm("output/lights.ppm")
+        .expect("Saving image");
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use spacer::camera::{Camera, CameraParams};
+use spacer::color::Color;
+use spacer::image::Image;
+use spacer::integrator::PathIntegrator;
+use spacer::light::{LightSampling, SphereLight};
+use spacer::material::Material;
+use spacer::math::{Transform, Vec3, vec3};
+use spacer::primitives::{BvhNode, HittableList, Sphere};
+use spacer::renderer::{MtRenderer, Renderer};
+use spacer::scene::Scene;
+
+const CANVAS_WIDTH: u32 = 800;
+const CANVAS_HEIGHT: u32 = 450;
+const LIGHT_GRID: i32 = 40;
+
+fn main() {
+    let light_sampling = match std::env::args().nth(1).as_deref() {
+        Some("uniform") => LightSampling::Uniform,
+        Some("power") => LightSampling::Power,
+        _ => LightSampling::Bvh,
+    };
+
+    let mut image = Image::new(CANVAS_WIDTH, CANVAS_HEIGHT);
+
+    let camera_params = CameraParams {
+        image_width: CANVAS_WIDTH,
+        image_height: CANVAS_HEIGHT,
+        fov: f32::to_radians(50.0),
+        samples_per_pixel: 8,
+        ..CameraParams::default()
+    };
+    let mut camera = Camera::new(camera_params);
+    camera.transform = Transform::look_at(vec3(0.0, 6.0, 16.0), vec3(0.0, 0.0, 0.0), Vec3::Y);
+
+    let mut world = HittableList::default();
+    world.add(Arc::new(Sphere {
+        center: vec3(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Material::lambertian(Color::new(0.5, 0.5, 0.5))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(-2.5, 1.5, 0.0),
+        radius: 1.5,
+        material: Arc::new(Material::lambertian(Color::new(0.8, 0.8, 0.8))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(2.5, 1.5, 0.0),
+        radius: 1.5,
+        material: Arc::new(Material::metalic(Color::new(0.8, 0.8, 0.8), 0.2)),
+    }));
+
+    // Grid of small colored lights, like a city at night
+    let mut lights = Vec::new();
+    for i in 0..LIGHT_GRID {
+        for j in 0..LIGHT_GRID {
+            let center = vec3(
+                (i - LIGHT_GRID / 2) as f32 + 0.8 * fastrand::f32(),
+                0.05 + 0.3 * fastrand::f32(),
+                (j - LIGHT_GRID / 2) as f32 + 0.8 * fastrand::f32(),
+            );
+            let radiance = Color::new(
+                0.5 + 0.5 * fastrand::f32(),
+                0.5 + 0.5 * fastrand::f32(),
+                0.5 + 0.5 * fastrand::f32(),
+            ) * 40.0;
+            let light = Arc::new(SphereLight::new(center, 0.05, radiance));
+            world.add(light.clone());
+            lights.push(light);
+        }
+    }
+
+    let mut scene = Scene::new(Arc::new(BvhNode::new(&mut world)));
+    for light in lights {
+        scene.add_light(light);
+    }
+    scene.set_light_sampling(light_sampling);
+
+    let integrator = PathIntegrator::new(8);
+    let render_timer = Instant::now();
+    let renderer = MtRenderer::default();
+    renderer.render(&camera, &mut image, |ray, sampler| {
+        integrator.radiance(&scene, ray, sampler)
+    });
+
+    let frame_time = render_timer.elapsed();
+    println!(
+        "Frame rendered with {:?} light sampling in {}ms",
+        light_sampling,
+        frame_time.as_millis()
+    );
+
+    image
+        .save_as_ppm("output/many_lights.ppm")
+        .expect("Saving image");
+}
@@ -1,15 +1,17 @@
 use std::sync::Arc;
-use std::time::Instant;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
-use spacer::camera::{Camera, CameraParams};
+use spacer::camera::{Camera, CameraParams, Filter};
 use spacer::color::Color;
 use spacer::image::{Image, RenderTarget};
+use spacer::integrator::PathIntegrator;
+use spacer::light::{SkyLight, SkyParams};
 use spacer::material::Material;
-use spacer::math::{Interval, Transform, Vec3, vec3};
-use spacer::primitives::{BvhNode, Hittable, HittableList, Ray, Sphere};
-use spacer::renderer::{MtRenderer, Renderer, StRenderer};
-
-const SKY_COLOR: Color = Color::new(0.5, 0.7, 1.0);
+use spacer::math::{Transform, Vec3, vec3};
+use spacer::primitives::{BvhNode, Hittable, HittableList, Sphere};
+use spacer::renderer::{MtRenderer, RenderHandle, Renderer};
+use spacer::scene::Scene;
 
 fn main() {
     init_logger(log::LevelFilter::Debug);
@@ -25,12 +27,21 @@ fn main() {
         samples_per_pixel: 32,
         defocus_angle: f32::to_radians(0.6),
         focus_dist: 10.0,
+        ..CameraParams::default()
     };
 
     let mut camera = Camera::new(camera_params);
     camera.transform = Transform::look_at(vec3(13.0, 2.0, 3.0), Vec3::ZERO, Vec3::Y);
+    camera.filter = Filter::mitchell(2.0);
 
-    let world = final_world();
+    let mut scene = Scene::new(Arc::new(final_world()));
+    let sky = SkyLight::new(SkyParams {
+        sun_elevation: f32::to_radians(35.0),
+        sun_azimuth: f32::to_radians(-60.0),
+        ..Default::default()
+    });
+    scene.add_light(Arc::new(sky.sun()));
+    scene.add_light(Arc::new(sky));
 
     log::info!(
         "Image resolution: {}x{}",
@@ -40,7 +51,32 @@ fn main() {
 
     let timer = Instant::now();
     let renderer = MtRenderer::default();
-    renderer.render(&camera, &mut image, |ray| ray_color(ray, &world, 50));
+    let integrator = PathIntegrator::new(50);
+    let handle = RenderHandle::new();
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    std::thread::scope(|s| {
+        let handle = &handle;
+        // Reports the progress until the render returns and drops the sender
+        s.spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = done_rx.recv_timeout(Duration::from_secs(2))
+            {
+                log::info!(
+                    "Progress: {:.1}%, ETA {:.0}s, {} rays",
+                    handle.progress(),
+                    handle.eta().unwrap_or_default().as_secs_f64(),
+                    handle.rays_traced()
+                );
+            }
+        });
+
+        renderer.render_with_handle(
+            &camera,
+            &mut image,
+            |ray, sampler| integrator.radiance(&scene, ray, sampler),
+            handle,
+        );
+        drop(done_tx);
+    });
     let render_time = timer.elapsed();
     log::info!("Render in: {:.6}s", render_time.as_secs_f64());
 
@@ -49,27 +85,9 @@ fn main() {
     log::info!("Image saved to {}", image_path);
 }
 
-fn ray_color(ray: Ray, world: &impl Hittable, bounces: u8) -> Color {
-    if bounces == 0 {
-        return Color::BLACK;
-    }
-
-    if let Some(hit) = ray.hit(world, Interval::new(0.001, f32::INFINITY)) {
-        if let Some((attenuation, scattered_ray)) = hit.material.scatter(&ray, &hit) {
-            return ray_color(scattered_ray, world, bounces - 1) * attenuation;
-        }
-
-        return Color::BLACK;
-    }
-
-    let ray_norm_dir = ray.direction().normalized();
-    let a = 0.5 * (ray_norm_dir.y + 1.0);
-    Color::WHITE.lerp(SKY_COLOR, a)
-}
-
 fn final_world() -> impl Hittable {
     let mut world = HittableList::default();
-    let ground_material = Material::lambertian(Color::new(0.5, 0.5, 0.5));
+    let ground_material = Arc::new(Material::lambertian(Color::new(0.5, 0.5, 0.5)));
     world.add(Arc::new(Sphere {
         center: vec3(0.0, -1000.0, 0.0),
         radius: 1000.0,
@@ -86,7 +104,7 @@ fn final_world() -> impl Hittable {
             );
 
             if (center - vec3(4.0, 0.2, 0.0)).length() > 0.9 {
-                let sphere_material = if choose_mat < 0.8 {
+                let sphere_material = Arc::new(if choose_mat < 0.8 {
                     let albedo = Color::random() * Color::random();
                     Material::lambertian(albedo)
                 } else if choose_mat < 0.95 {
@@ -95,7 +113,7 @@ fn final_world() -> impl Hittable {
                     Material::metalic(albedo, fuzz)
                 } else {
                     Material::dielectric(1.5)
-                };
+                });
 
                 world.add(Arc::new(Sphere {
                     center,
@@ -106,21 +124,21 @@ fn final_world() -> impl Hittable {
         }
     }
 
-    let material1 = Material::dielectric(1.5);
+    let material1 = Arc::new(Material::dielectric(1.5));
     world.add(Arc::new(Sphere {
         center: vec3(0.0, 1.0, 0.0),
         radius: 1.0,
         material: material1,
     }));
 
-    let material2 = Material::lambertian(Color::new(0.4, 0.2, 0.1));
+    let material2 = Arc::new(Material::lambertian(Color::new(0.4, 0.2, 0.1)));
     world.add(Arc::new(Sphere {
         center: vec3(-4.0, 1.0, 0.0),
         radius: 1.0,
         material: material2,
     }));
 
-    let material3 = Material::metalic(Color::new(0.7, 0.6, 0.5), 0.0);
+    let material3 = Arc::new(Material::metalic(Color::new(0.7, 0.6, 0.5), 0.0));
     world.add(Arc::new(Sphere {
         center: vec3(4.0, 1.0, 0.0),
         radius: 1.0,
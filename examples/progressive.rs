@@ -0,0 +1,88 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use spacer::camera::{Camera, CameraParams};
+use spacer::color::Color;
+use spacer::image::Image;
+use spacer::integrator::PathIntegrator;
+use spacer::light::{SkyLight, SkyParams};
+use spacer::material::Material;
+use spacer::math::{Transform, Vec3, vec3};
+use spacer::primitives::{BvhNode, HittableList, Sphere};
+use spacer::renderer::{MtRenderer, RenderHandle, Renderer, StopConditions};
+use spacer::scene::Scene;
+
+const CANVAS_WIDTH: u32 = 640;
+const CANVAS_HEIGHT: u32 = 360;
+
+fn main() {
+    let mut image = Image::new(CANVAS_WIDTH, CANVAS_HEIGHT);
+
+    let camera_params = CameraParams {
+        image_width: CANVAS_WIDTH,
+        image_height: CANVAS_HEIGHT,
+        fov: f32::to_radians(40.0),
+        samples_per_pixel: 64,
+        ..CameraParams::default()
+    };
+    let mut camera = Camera::new(camera_params);
+    camera.transform = Transform::look_at(vec3(0.0, 2.0, 8.0), vec3(0.0, 0.5, 0.0), Vec3::Y);
+
+    let mut world = HittableList::default();
+    world.add(Arc::new(Sphere {
+        center: vec3(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Material::lambertian(Color::new(0.5, 0.5, 0.5))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(-1.1, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::lambertian(Color::new(0.8, 0.3, 0.3))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(1.1, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::metalic(Color::new(0.8, 0.8, 0.8), 0.2)),
+    }));
+
+    let mut scene = Scene::new(Arc::new(BvhNode::new(&mut world)));
+    let sky = SkyLight::new(SkyParams::default());
+    scene.add_light(Arc::new(sky.sun()));
+    scene.add_light(Arc::new(sky));
+
+    // Stops at the first of 256 passes, 10 seconds or 5% noise
+    let stop = StopConditions {
+        max_passes: Some(256),
+        time_budget: Some(Duration::from_secs(10)),
+        target_noise: Some(0.05),
+    };
+
+    let integrator = PathIntegrator::new(8);
+    let renderer = MtRenderer::default();
+    let last_pass = renderer.render_progressive(
+        &camera,
+        &mut image,
+        |ray, sampler| integrator.radiance(&scene, ray, sampler),
+        stop,
+        &RenderHandle::new(),
+        |pass, image| {
+            println!(
+                "Pass {} after {}ms, noise {:?}",
+                pass.count,
+                pass.elapsed.as_millis(),
+                pass.noise
+            );
+            // Previews the image with the samples so far
+            if pass.count.is_power_of_two() {
+                image
+                    .save_as_ppm("output/progressive.ppm")
+                    .expect("Saving image");
+            }
+        },
+    );
+    println!("Rendered {} passes", last_pass.count);
+
+    image
+        .save_as_ppm("output/progressive.ppm")
+        .expect("Saving image");
+}
@@ -19,7 +19,7 @@ fn main() {
         image_width: CANVAS_WIDTH,
         image_height: CANVAS_HEIGHT,
         fov: f32::to_radians(90.0),
-        ..Default::default()
+        ..CameraParams::default()
     };
     let camera = Camera::new(camera_params);
     log::info!("Aspect ratio: {}", camera.aspect_ratio());
@@ -28,18 +28,18 @@ fn main() {
     world.add(Arc::new(Sphere {
         center: Vec3::new(0.0, 0.0, -1.0),
         radius: 0.5,
-        material: Material::lambertian(Color::RED),
+        material: Arc::new(Material::lambertian(Color::RED)),
     }));
     world.add(Arc::new(Sphere {
         center: Vec3::new(0.0, -100.5, -1.0),
         radius: 100.0,
-        material: Material::lambertian(Color::GREEN),
+        material: Arc::new(Material::lambertian(Color::GREEN)),
     }));
 
     let render_timer = Instant::now();
-    let renderer = StRenderer;
-    renderer.render(&camera, &mut image, |ray| {
-        ray_color(&world, ray, Interval::new(0.0, f32::INFINITY))
+    let renderer = StRenderer::default();
+    renderer.render(&camera, &mut image, |ray, _| {
+        ray_color(&world, ray, Interval::new(0.0, ray.t_max()))
     });
 
     let frame_time = render_timer.elapsed();
@@ -1,9 +1,87 @@
-use std::f32::consts::FRAC_PI_4;
+mod adaptive;
+mod aperture;
+mod filter;
+mod lens;
+
+pub use adaptive::*;
+pub use aperture::*;
+pub use filter::*;
+pub use lens::*;
+
+use std::f32::consts::{FRAC_PI_4, PI};
+use std::ops::Range;
 
 use crate::color::Color;
-use crate::image::RenderTarget;
+use crate::image::{Film, PixelBounds};
 use crate::math::{Mat3, Transform, Vec2, Vec3};
 use crate::primitives::Ray;
+use crate::sampler::{Sampler, Sampling};
+
+/// Mapping of the viewport to camera rays.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    /// Pinhole perspective projection with the vertical field of view `fov`.
+    #[default]
+    Perspective,
+    /// Parallel rays along the view direction.
+    Orthographic {
+        /// The height of the viewed region in world units.
+        view_height: f32,
+    },
+    /// Circular fisheye fitted into the shorter side of the image, pixels outside of it are black.
+    Fisheye {
+        mapping: FisheyeMapping,
+        /// The full field of view of the image circle in radians, up to `2 * PI`.
+        fov: f32,
+    },
+    /// Full sphere of directions, longitude along the width and latitude along the height.
+    Equirectangular,
+}
+
+/// Mapping of the angle from the view direction to the distance from the fisheye image center.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FisheyeMapping {
+    /// Distance is proportional to the angle.
+    #[default]
+    Equidistant,
+    /// Preserves the solid angle of image areas.
+    Equisolid,
+}
+
+/// Arrangement of the two eye views in a stereo image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// Left eye in the left half, right eye in the right half.
+    #[default]
+    SideBySide,
+    /// Left eye in the top half, right eye in the bottom half.
+    TopBottom,
+}
+
+/// Parameters of stereo rendering.
+///
+/// With the equirectangular projection the eyes orbit the camera position,
+/// producing an omni-directional stereo (ODS) panorama.
+#[derive(Clone, Copy, Debug)]
+pub struct StereoParams {
+    /// The interpupillary distance between the eyes in world units.
+    pub ipd: f32,
+    /// The distance in world units where the views of both eyes converge (zero parallax).
+    ///
+    /// Used by the perspective projection, views are shifted off-axis instead of toed-in.
+    pub convergence_dist: f32,
+    pub layout: StereoLayout,
+}
+
+impl Default for StereoParams {
+    fn default() -> Self {
+        Self {
+            ipd: 0.064,
+            convergence_dist: 10.0,
+            layout: StereoLayout::SideBySide,
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct CameraParams {
@@ -11,26 +89,123 @@ pub struct CameraParams {
     pub image_width: u32,
     /// The height of the image in pixels.
     pub image_height: u32,
-    /// The vertical field of view (FOV) in radians.
+    /// The projection of the camera.
+    pub projection: Projection,
+    /// The vertical field of view (FOV) in radians, used by the perspective projection.
     pub fov: f32,
     /// The distance from the camera in world units of the viewing frustum’s near plane.
-    // pub near: f32,
+    ///
+    /// Measured along the view direction, or along the ray for fisheye and equirectangular projections.
+    pub near: f32,
     /// The distance from the camera in world units of the viewing frustum’s far plane.
-    // pub far: f32,
+    ///
+    /// Rays are not intersected beyond it and see the background instead.
+    pub far: f32,
     /// The number of samples of single viewport pixel.
     pub samples_per_pixel: u16,
     /// The distance to the viewport in world units (also focus distance).
     pub focus_dist: f32,
     /// The variation angle of rays through each pixel in radians.
     pub defocus_angle: f32,
+    /// Renders views of both eyes into one image if set.
+    pub stereo: Option<StereoParams>,
+    /// The time in seconds when the shutter opens, rays are spread over the shutter interval.
+    pub shutter_open: f32,
+    /// The time in seconds when the shutter closes.
+    pub shutter_close: f32,
+    /// The scale applied to the radiance arriving at the camera.
+    pub exposure: f32,
 }
 
+/// Parameters of a real camera, mapped to [`CameraParams`] assuming world units in meters.
 #[derive(Clone, Copy, Debug)]
+pub struct PhysicalCameraParams {
+    /// The ratio of the focal length to the aperture diameter.
+    pub f_number: f32,
+    /// The focal length of the lens in millimeters.
+    pub focal_length: f32,
+    /// The width and height of the sensor in millimeters.
+    ///
+    /// The image covers the largest centered region of the sensor with its aspect ratio,
+    /// so a wider image is cropped from the top and bottom and a narrower one from the sides.
+    pub sensor_size: Vec2,
+    /// The time in seconds the shutter stays open.
+    pub shutter_speed: f32,
+    /// The sensitivity of the sensor.
+    pub iso: f32,
+    /// The distance to the focus plane in meters.
+    pub focus_dist: f32,
+}
+
+impl Default for PhysicalCameraParams {
+    fn default() -> Self {
+        Self {
+            f_number: 8.0,
+            focal_length: 50.0,
+            // Full frame sensor
+            sensor_size: Vec2::new(36.0, 24.0),
+            shutter_speed: 1.0 / 125.0,
+            iso: 100.0,
+            focus_dist: 10.0,
+        }
+    }
+}
+
+impl PhysicalCameraParams {
+    /// Returns the camera parameters for an image of the given size.
+    ///
+    /// The exposure maps scene luminance in cd/m^2 to pixel values,
+    /// so that the saturation of the sensor is at `1.0`.
+    pub fn to_camera_params(&self, image_width: u32, image_height: u32) -> CameraParams {
+        // The side of the sensor limiting the image sets the height of the covered region
+        let aspect_ratio = image_width as f32 / image_height as f32;
+        let film_height = self.sensor_size.y.min(self.sensor_size.x / aspect_ratio);
+        let fov = 2.0 * f32::atan(film_height / (2.0 * self.focal_length));
+
+        // Aperture diameter converted from millimeters to meters
+        let aperture_radius = self.focal_length / self.f_number / 2.0 * 1e-3;
+        let defocus_angle = 2.0 * f32::atan(aperture_radius / self.focus_dist);
+
+        // Saturation based sensitivity, EV100 = log2(N^2 / t * 100 / ISO)
+        let ev100 =
+            f32::log2(self.f_number * self.f_number / self.shutter_speed * 100.0 / self.iso);
+        let exposure = 1.0 / (1.2 * f32::exp2(ev100));
+
+        CameraParams {
+            image_width,
+            image_height,
+            fov,
+            focus_dist: self.focus_dist,
+            defocus_angle,
+            shutter_open: 0.0,
+            shutter_close: self.shutter_speed,
+            exposure,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Camera {
     params: CameraParams,
     viewport: Viewport,
     /// Camera global transform.
     pub transform: Transform,
+    /// Shape of the lens aperture, used with a positive `defocus_angle`.
+    pub aperture: Aperture,
+    /// Lens system replacing the thin lens of the perspective projection,
+    /// focused at the distance it was created with instead of `focus_dist`.
+    pub lens_system: Option<LensSystem>,
+    /// Filter reconstructing pixels from the samples around them.
+    pub filter: Filter,
+    /// Generator of the sample values of camera rays and the paths they start.
+    pub sampling: Sampling,
+    /// Additional samples for noisy pixels, `None` renders the same samples in every pixel.
+    pub adaptive: Option<AdaptiveSampling>,
+    /// Seed of the sample values, every pixel sample has its own stream derived from it.
+    ///
+    /// Renders with the same seed and tile size are identical regardless of the renderer and its workers.
+    pub seed: u64,
 }
 
 impl Default for CameraParams {
@@ -38,41 +213,123 @@ impl Default for CameraParams {
         Self {
             image_width: 1,
             image_height: 1,
+            projection: Projection::Perspective,
             fov: FRAC_PI_4,
-            // near: 0.1,
-            // far: 1000.0,
+            near: 0.0,
+            far: f32::INFINITY,
             samples_per_pixel: 1,
             focus_dist: 1.0,
             defocus_angle: 0.0,
+            stereo: None,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            exposure: 1.0,
         }
     }
 }
 
 impl Camera {
     pub fn new(params: CameraParams) -> Self {
+        let (eye_width, eye_height) = Self::eye_size(&params);
         Self {
             params,
             viewport: Viewport::new(
+                params.projection,
                 params.fov,
                 params.focus_dist,
                 params.defocus_angle,
-                params.image_width,
-                params.image_height,
+                eye_width,
+                eye_height,
             ),
             transform: Transform::default(),
+            aperture: Aperture::default(),
+            lens_system: None,
+            filter: Filter::default(),
+            sampling: Sampling::default(),
+            adaptive: None,
+            seed: 0,
         }
     }
 
+    /// Aspect ratio of the view of a single eye.
     pub fn aspect_ratio(&self) -> f32 {
         self.viewport.aspect_ratio
     }
+
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.params.samples_per_pixel as u32
+    }
+
+    /// Size in pixels of the view of a single eye.
+    fn eye_size(params: &CameraParams) -> (u32, u32) {
+        match params.stereo.map(|stereo| stereo.layout) {
+            None => (params.image_width, params.image_height),
+            Some(StereoLayout::SideBySide) => {
+                ((params.image_width / 2).max(1), params.image_height)
+            }
+            Some(StereoLayout::TopBottom) => (params.image_width, (params.image_height / 2).max(1)),
+        }
+    }
+
+    /// Maps an image pixel to the pixel of an eye view and the side of the eye,
+    /// `-1.0` for the left eye, `1.0` for the right one and `0.0` without stereo.
+    fn eye_pixel(&self, x: u32, y: u32) -> Option<(u32, u32, f32)> {
+        let (eye_width, eye_height) = Self::eye_size(&self.params);
+        let (x, y, side) = match self.params.stereo.map(|stereo| stereo.layout) {
+            None => return Some((x, y, 0.0)),
+            Some(StereoLayout::SideBySide) if x < eye_width => (x, y, -1.0),
+            Some(StereoLayout::SideBySide) => (x - eye_width, y, 1.0),
+            Some(StereoLayout::TopBottom) if y < eye_height => (x, y, -1.0),
+            Some(StereoLayout::TopBottom) => (x, y - eye_height, 1.0),
+        };
+
+        // Odd image sizes leave a line not covered by either eye
+        (x < eye_width && y < eye_height).then_some((x, y, side))
+    }
+
+    /// Maps a pixel of the eye view on the `side` back to the image pixel, inverse of [`Self::eye_pixel`].
+    fn image_pixel(&self, x: u32, y: u32, side: f32) -> (u32, u32) {
+        let (eye_width, eye_height) = Self::eye_size(&self.params);
+        match self.params.stereo.map(|stereo| stereo.layout) {
+            Some(StereoLayout::SideBySide) if side > 0.0 => (x + eye_width, y),
+            Some(StereoLayout::TopBottom) if side > 0.0 => (x, y + eye_height),
+            _ => (x, y),
+        }
+    }
+
+    /// The number of pixels around a pixel its samples are splatted into.
+    pub(crate) fn filter_margin(&self) -> u32 {
+        let radius = self.filter.radius();
+        radius.x.max(radius.y).ceil() as u32
+    }
+
+    /// Camera space offset of the eye on the `side` from the camera position.
+    fn eye_offset(&self, side: f32) -> f32 {
+        self.params
+            .stereo
+            .map_or(0.0, |stereo| side * stereo.ipd / 2.0)
+    }
+}
+
+/// Sample values of a single camera ray.
+#[derive(Clone, Copy, Debug)]
+struct CameraSample {
+    /// Offset from the pixel center in `[-0.5, 0.5)^2`.
+    offset: Vec2,
+    /// Fraction of the shutter interval.
+    time: f32,
+    /// Point on the lens aperture.
+    lens: Vec2,
 }
 
 #[derive(Clone, Copy, Debug)]
 struct Viewport {
-    // width: f32,
-    // height: f32,
     aspect_ratio: f32,
+    is_orthographic: bool,
+    /// Unit vector to the right of the camera.
+    right: Vec3,
+    /// Vector from the camera to the center of the viewport.
+    forward: Vec3,
     pixel00_center: Vec3,
     pixel_delta_u: Vec3,
     pixel_delta_v: Vec3,
@@ -82,6 +339,7 @@ struct Viewport {
 
 impl Viewport {
     fn new(
+        projection: Projection,
         fov: f32,
         focus_dist: f32,
         defocus_angle: f32,
@@ -89,8 +347,10 @@ impl Viewport {
         image_height: u32,
     ) -> Self {
         let aspect_ratio = image_width as f32 / image_height as f32;
-        let h = f32::tan(fov / 2.0);
-        let height = 2.0 * h * focus_dist;
+        let height = match projection {
+            Projection::Orthographic { view_height } => view_height,
+            _ => 2.0 * f32::tan(fov / 2.0) * focus_dist,
+        };
         let width = height * aspect_ratio;
 
         let pixel_delta_u = Vec3::new(width / image_width as f32, 0.0, 0.0);
@@ -103,9 +363,10 @@ impl Viewport {
         let defocus_disk_v = Vec3::new(0.0, defocus_radius, 0.0);
 
         Self {
-            // width,
-            // height,
             aspect_ratio,
+            is_orthographic: matches!(projection, Projection::Orthographic { .. }),
+            right: Vec3::X,
+            forward: Vec3::new(0.0, 0.0, -focus_dist),
             pixel_delta_u,
             pixel_delta_v,
             pixel00_center,
@@ -118,6 +379,9 @@ impl Viewport {
     fn rotated(&self, rotation: Mat3) -> Self {
         Self {
             aspect_ratio: self.aspect_ratio,
+            is_orthographic: self.is_orthographic,
+            right: rotation * self.right,
+            forward: rotation * self.forward,
             pixel00_center: rotation * self.pixel00_center,
             pixel_delta_u: rotation * self.pixel_delta_u,
             pixel_delta_v: rotation * self.pixel_delta_v,
@@ -133,31 +397,179 @@ impl Viewport {
 }
 
 impl Camera {
-    pub(crate) fn render_to<R, F>(&self, target: &mut R, ray_color: F)
-    where
-        R: RenderTarget,
-        F: Fn(Ray) -> Color,
+    /// Renders the `samples` of the image pixels within the `bounds` into the `film`, which should
+    /// cover the bounds extended by [`Self::filter_margin`] to not lose splatted samples.
+    ///
+    /// Sample indices beyond the samples per pixel continue the sequences of the samplers,
+    /// adaptive sampling adds batches of them to noisy pixels.
+    pub(crate) fn render_to<F>(
+        &self,
+        film: &mut Film,
+        bounds: &PixelBounds,
+        samples: Range<u32>,
+        ray_color: F,
+    ) where
+        F: Fn(Ray, &mut dyn Sampler) -> Color,
     {
-        let sample_scale = f32::recip(self.params.samples_per_pixel as f32);
         let rotated_viewport = self.viewport.rotated(self.transform.rotation);
+        let mut sampler = self.sampling.create(self.samples_per_pixel(), self.seed);
+
+        for (x, y) in bounds.pixels() {
+            let Some((ex, ey, side)) = self.eye_pixel(x, y) else {
+                continue;
+            };
+
+            let mut stats = PixelStats::default();
+            let mut batch = samples.clone();
+            loop {
+                for index in batch.clone() {
+                    sampler.start_pixel_sample((x, y), index);
+                    let sample = CameraSample {
+                        offset: sampler.get_pixel_2d() - Vec2::splat(0.5),
+                        time: sampler.get_1d(),
+                        lens: sampler.get_2d(),
+                    };
+
+                    // Samples outside of the projection still count as black
+                    let color = self
+                        .sample_ray(ex, ey, side, &sample, &rotated_viewport)
+                        .map_or(Color::BLACK, |(ray, weight)| {
+                            ray_color(ray, sampler.as_mut()) * weight
+                        })
+                        * self.params.exposure;
+                    stats.add(color.luminance());
+                    self.splat(film, ex, ey, sample.offset, side, color);
+                }
+
+                let rendered = samples.start..batch.end;
+                match self
+                    .adaptive
+                    .and_then(|adaptive| adaptive.next_batch(rendered, &stats))
+                {
+                    Some(next) => batch = next,
+                    None => break,
+                }
+            }
+            film.add_sample_count(x, y, stats.count);
+        }
+    }
+
+    /// Adds the sample at `offset` from the center of the eye pixel `(x, y)`
+    /// to all pixels of the same eye view within the filter radius.
+    fn splat(&self, film: &mut Film, x: u32, y: u32, offset: Vec2, side: f32, color: Color) {
+        let (eye_width, eye_height) = Self::eye_size(&self.params);
+        let radius = self.filter.radius();
+        let p = Vec2::new(x as f32 + 0.5 + offset.x, y as f32 + 0.5 + offset.y);
+
+        let x0 = ((p.x - 0.5 - radius.x).ceil() as i32).max(0);
+        let x1 = ((p.x - 0.5 + radius.x).floor() as i32).min(eye_width as i32 - 1);
+        let y0 = ((p.y - 0.5 - radius.y).ceil() as i32).max(0);
+        let y1 = ((p.y - 0.5 + radius.y).floor() as i32).min(eye_height as i32 - 1);
 
-        for y in 0..target.get_height() {
-            for x in 0..target.get_width() {
-                let mut color = Color::BLACK;
-                let (rx, ry) = target.coordinate(x, y);
-                for _ in 0..self.params.samples_per_pixel {
-                    let ray = self.sample_ray(rx, ry, &rotated_viewport);
-                    color += ray_color(ray);
+        for py in y0..=y1 {
+            for px in x0..=x1 {
+                let center = Vec2::new(px as f32 + 0.5, py as f32 + 0.5);
+                let weight = self.filter.evaluate(center - p);
+                if weight != 0.0 {
+                    let (ix, iy) = self.image_pixel(px as u32, py as u32, side);
+                    film.add_sample(ix, iy, color, weight);
                 }
-                color *= sample_scale;
-                target.put_pixel(x, y, color);
             }
         }
     }
 
+    /// Samples a ray through the pixel `(x, y)` of the eye view on the `side` and the weight of its radiance,
+    /// `None` if the pixel is not covered by the projection.
     #[inline]
-    fn sample_ray(&self, x: u32, y: u32, viewport: &Viewport) -> Ray {
-        let offset = Vec2::random_in_square() - Vec2::splat(0.5);
+    fn sample_ray(
+        &self,
+        x: u32,
+        y: u32,
+        side: f32,
+        sample: &CameraSample,
+        viewport: &Viewport,
+    ) -> Option<(Ray, f32)> {
+        let offset = sample.offset;
+        let eye_offset = self.eye_offset(side);
+        let mut weight = 1.0;
+        let (origin, direction) = match (self.params.projection, &self.lens_system) {
+            (Projection::Perspective, Some(lens_system)) => {
+                let film = self.film_position(x, y, offset);
+                let film = Vec2::new(2.0 * film.x - 1.0, 1.0 - 2.0 * film.y);
+                let (origin, dir, lens_weight) =
+                    lens_system.sample_ray(film, viewport.aspect_ratio, sample.lens)?;
+                weight = lens_weight;
+                let origin = origin + Vec3::X * eye_offset;
+                (
+                    self.transform.translation + self.transform.rotation * origin,
+                    self.transform.rotation * dir,
+                )
+            }
+            (Projection::Perspective | Projection::Orthographic { .. }, _) => {
+                self.viewport_ray(x, y, offset, sample.lens, eye_offset, viewport)
+            }
+            (Projection::Fisheye { mapping, fov }, _) => {
+                let dir = self.fisheye_direction(x, y, offset, mapping, fov)?;
+                let origin = Vec3::X * eye_offset;
+                (
+                    self.transform.translation + self.transform.rotation * origin,
+                    self.transform.rotation * dir,
+                )
+            }
+            (Projection::Equirectangular, _) => {
+                let dir = self.equirectangular_direction(x, y, offset);
+                // Eyes orbit the camera, always perpendicular to the horizontal view direction
+                let right = Vec3::new(-dir.z, 0.0, dir.x);
+                let right = if right.length_squared() > 0.0 {
+                    right.normalized()
+                } else {
+                    Vec3::X
+                };
+                (
+                    self.transform.translation + self.transform.rotation * (right * eye_offset),
+                    self.transform.rotation * dir,
+                )
+            }
+        };
+
+        let (t_near, t_far) = self.clip_distances(direction)?;
+        let time = self.params.shutter_open
+            + (self.params.shutter_close - self.params.shutter_open) * sample.time;
+        let ray =
+            Ray::with_time(origin + direction * t_near, direction, time).with_t_max(t_far - t_near);
+        Some((ray, weight))
+    }
+
+    /// Returns the ray parameters of the near and far clipping planes along the world space `direction`.
+    #[inline]
+    fn clip_distances(&self, direction: Vec3) -> Option<(f32, f32)> {
+        let scale = match self.params.projection {
+            // Planes perpendicular to the view direction
+            Projection::Perspective | Projection::Orthographic { .. } => {
+                let forward = self.transform.rotation * -Vec3::Z;
+                direction.dot(&forward)
+            }
+            // Spheres around the camera, as rays may look sideways or backwards
+            Projection::Fisheye { .. } | Projection::Equirectangular => direction.length(),
+        };
+        if scale <= 0.0 {
+            return None;
+        }
+
+        Some((self.params.near / scale, self.params.far / scale))
+    }
+
+    /// Returns the origin and direction of a ray through the viewport.
+    #[inline]
+    fn viewport_ray(
+        &self,
+        x: u32,
+        y: u32,
+        offset: Vec2,
+        lens: Vec2,
+        eye_offset: f32,
+        viewport: &Viewport,
+    ) -> (Vec3, Vec3) {
         let pixel_center = viewport.pixel_center(x, y);
         let pixel_sample =
             pixel_center + viewport.pixel_delta_u * offset.x + viewport.pixel_delta_v * offset.y;
@@ -165,18 +577,96 @@ impl Camera {
         let jittered_origin = if self.params.defocus_angle <= 0.0 {
             Vec3::ZERO
         } else {
-            self.defocus_disk_sample(viewport)
+            self.defocus_disk_sample(lens, viewport)
+        };
+
+        // Orthographic rays start on the plane through the camera, parallel to the viewport
+        let lens_center = if viewport.is_orthographic {
+            pixel_sample - viewport.forward
+        } else {
+            Vec3::ZERO
+        };
+
+        let eye = viewport.right * eye_offset;
+        // Off-axis stereo shares the viewport at the convergence distance, shifted to the focus distance
+        let convergence_shift = match self.params.stereo {
+            Some(stereo) if !viewport.is_orthographic => {
+                eye * (1.0 - self.params.focus_dist / stereo.convergence_dist)
+            }
+            _ => eye,
+        };
+
+        let ray_origin = self.transform.translation + lens_center + eye + jittered_origin;
+        let ray_direction = pixel_sample + convergence_shift - lens_center - eye - jittered_origin;
+
+        (ray_origin, ray_direction)
+    }
+
+    /// Returns the position of the sample in the eye view normalized to `[0.0, 1.0]`.
+    #[inline]
+    fn film_position(&self, x: u32, y: u32, offset: Vec2) -> Vec2 {
+        let (width, height) = Self::eye_size(&self.params);
+        Vec2::new(
+            (x as f32 + 0.5 + offset.x) / width as f32,
+            (y as f32 + 0.5 + offset.y) / height as f32,
+        )
+    }
+
+    /// Camera space direction of a fisheye sample, `None` outside of the image circle.
+    fn fisheye_direction(
+        &self,
+        x: u32,
+        y: u32,
+        offset: Vec2,
+        mapping: FisheyeMapping,
+        fov: f32,
+    ) -> Option<Vec3> {
+        let film = self.film_position(x, y, offset);
+        let (width, height) = Self::eye_size(&self.params);
+        let (width, height) = (width as f32, height as f32);
+        let radius = width.min(height) / 2.0;
+
+        // Pixel offsets from the image center relative to the circle radius, `y` up
+        let px = (film.x - 0.5) * width / radius;
+        let py = (0.5 - film.y) * height / radius;
+        let r = (px * px + py * py).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+
+        let theta_max = (fov / 2.0).min(PI);
+        let theta = match mapping {
+            FisheyeMapping::Equidistant => r * theta_max,
+            FisheyeMapping::Equisolid => 2.0 * f32::asin((r * f32::sin(theta_max / 2.0)).min(1.0)),
+        };
+
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let (sin_phi, cos_phi) = if r > 0.0 {
+            (py / r, px / r)
+        } else {
+            (0.0, 1.0)
         };
+        Some(Vec3::new(
+            sin_theta * cos_phi,
+            sin_theta * sin_phi,
+            -cos_theta,
+        ))
+    }
 
-        let ray_origin = self.transform.translation + jittered_origin;
-        let ray_direction = pixel_sample - jittered_origin;
+    /// Camera space direction of an equirectangular sample, the image center looks along `-Z`.
+    fn equirectangular_direction(&self, x: u32, y: u32, offset: Vec2) -> Vec3 {
+        let film = self.film_position(x, y, offset);
+        let phi = (film.x - 0.5) * 2.0 * PI;
+        let theta = film.y * PI;
 
-        Ray::new(ray_origin, ray_direction)
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        Vec3::new(sin_theta * sin_phi, cos_theta, -sin_theta * cos_phi)
     }
 
     #[inline]
-    fn defocus_disk_sample(&self, viewport: &Viewport) -> Vec3 {
-        let p = Vec2::random_in_disk();
+    fn defocus_disk_sample(&self, u: Vec2, viewport: &Viewport) -> Vec3 {
+        let p = self.aperture.sample(u);
         viewport.defocus_disk_u * p.x + viewport.defocus_disk_v * p.y
     }
 }
@@ -0,0 +1,87 @@
+use std::ops::Range;
+
+/// Spends additional samples only on pixels whose estimated error is above a threshold.
+///
+/// The samples per pixel of the camera are rendered first, then more batches of the same size
+/// until the error of the pixel falls below the `threshold` or it reaches `max_samples`.
+/// Progressive passes of a single sample have no error estimate and are not refined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveSampling {
+    /// The maximum number of samples of a pixel.
+    pub max_samples: u32,
+    /// Relative standard error of the mean pixel luminance at which a pixel is converged.
+    pub threshold: f32,
+    /// Writes a heatmap of the number of samples of each pixel instead of the image,
+    /// from blue at the samples per pixel to red at `max_samples`.
+    pub show_sample_counts: bool,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        Self {
+            max_samples: 256,
+            threshold: 0.05,
+            show_sample_counts: false,
+        }
+    }
+}
+
+impl AdaptiveSampling {
+    pub fn new(max_samples: u32, threshold: f32) -> Self {
+        Self {
+            max_samples,
+            threshold,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the batch of sample indices following the `rendered` ones,
+    /// `None` if the pixel is converged or has the maximum number of samples.
+    ///
+    /// Single samples are never refined, the following indices belong to the next progressive passes.
+    pub(crate) fn next_batch(
+        &self,
+        rendered: Range<u32>,
+        stats: &PixelStats,
+    ) -> Option<Range<u32>> {
+        if stats.count < 2 || stats.relative_error() <= self.threshold {
+            return None;
+        }
+
+        let batch = rendered.len() as u32;
+        let end = rendered.start + self.max_samples;
+        let next = rendered.end..(rendered.end + batch).min(end);
+        (!next.is_empty()).then_some(next)
+    }
+}
+
+/// Running mean and variance of the sample luminance of a pixel (Welford's algorithm).
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PixelStats {
+    pub count: u32,
+    mean: f32,
+    /// Sum of the squared differences from the mean.
+    m2: f32,
+}
+
+impl PixelStats {
+    /// Stops the relative error from growing without bound in black pixels.
+    const BLACK_LEVEL: f32 = 0.01;
+
+    pub fn add(&mut self, luminance: f32) {
+        self.count += 1;
+        let delta = luminance - self.mean;
+        self.mean += delta / self.count as f32;
+        self.m2 += delta * (luminance - self.mean);
+    }
+
+    /// Standard error of the mean relative to it, infinite with less than two samples.
+    pub fn relative_error(&self) -> f32 {
+        if self.count < 2 {
+            return f32::INFINITY;
+        }
+
+        let variance = self.m2 / (self.count - 1) as f32;
+        (variance / self.count as f32).sqrt() / (self.mean.abs() + Self::BLACK_LEVEL)
+    }
+}
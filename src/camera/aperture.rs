@@ -0,0 +1,113 @@
+use std::f32::consts::PI;
+use std::io;
+use std::path::Path;
+
+use crate::math::{Distribution2D, Vec2};
+use crate::sampler::{ONE_MINUS_EPSILON, sample_uniform_disk};
+use crate::texture::{ColorSpace, Texture};
+
+/// Shape of the lens aperture, visible in out-of-focus highlights (bokeh).
+#[derive(Clone, Debug, Default)]
+pub enum Aperture {
+    #[default]
+    Circular,
+    /// Regular polygon formed by the diaphragm blades.
+    Polygonal {
+        /// The number of blades, at least 3.
+        blades: u32,
+        /// The rotation of the polygon in radians.
+        rotation: f32,
+    },
+    /// Arbitrary shape given by an image.
+    Mask(ApertureMask),
+}
+
+/// Aperture shape sampled proportionally to the luminance of an image.
+///
+/// The shape is scaled to touch the unit circle, so the lens radius means the same as for other apertures.
+#[derive(Clone, Debug)]
+pub struct ApertureMask {
+    distribution: Distribution2D,
+    /// Extent of the image fitted into the unit disk.
+    scale: Vec2,
+}
+
+impl ApertureMask {
+    pub fn new(texture: &Texture) -> Self {
+        let width = texture.get_width();
+        let height = texture.get_height();
+
+        let mut func = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                func.push(texture.texel(x, y).luminance().max(0.0));
+            }
+        }
+
+        let size = width.max(height) as f32;
+        let scale = Vec2::new(width as f32 / size, height as f32 / size);
+
+        // Distance in `[0.0, 1.0]` of the farther edge of the texel `i` of `n` from the center
+        let edge = |i: u32, n: u32| {
+            let (start, end) = ((2 * i) as f32 - n as f32, (2 * i + 2) as f32 - n as f32);
+            start.abs().max(end.abs()) / n as f32
+        };
+        // The farthest corner of an open texel
+        let mut radius = 0.0f32;
+        for y in 0..height {
+            for x in 0..width {
+                if func[(y * width + x) as usize] > 0.0 {
+                    let corner = Vec2::new(edge(x, width) * scale.x, edge(y, height) * scale.y);
+                    radius = radius.max(corner.length());
+                }
+            }
+        }
+
+        Self {
+            distribution: Distribution2D::new(&func, width as usize, height as usize),
+            scale: if radius > 0.0 { scale / radius } else { scale },
+        }
+    }
+
+    pub fn load_ppm<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Texture::load_ppm(path, ColorSpace::Linear).map(|texture| Self::new(&texture))
+    }
+
+    /// Samples a point of the mask within the unit disk, `y` up.
+    fn sample(&self, u: Vec2) -> Vec2 {
+        let ((u, v), _) = self.distribution.sample_continuous(u.x, u.y);
+        Vec2::new(
+            (2.0 * u - 1.0) * self.scale.x,
+            (1.0 - 2.0 * v) * self.scale.y,
+        )
+    }
+}
+
+impl Aperture {
+    /// Maps the uniform sample `u` to a point of the aperture within the unit disk.
+    pub fn sample(&self, u: Vec2) -> Vec2 {
+        match self {
+            Self::Circular => sample_uniform_disk(u),
+            Self::Polygonal { blades, rotation } => sample_polygon((*blades).max(3), *rotation, u),
+            Self::Mask(mask) => mask.sample(u),
+        }
+    }
+}
+
+/// Uniformly samples a regular polygon inscribed in the unit circle.
+fn sample_polygon(sides: u32, rotation: f32, u: Vec2) -> Vec2 {
+    // All triangles fanning out from the center have the same area
+    let scaled = u.x * sides as f32;
+    let side = (scaled as u32).min(sides - 1);
+    let u0 = (scaled - side as f32).min(ONE_MINUS_EPSILON);
+    let angle = 2.0 * PI / sides as f32;
+    let a = rotation + side as f32 * angle;
+    let (sin_a, cos_a) = a.sin_cos();
+    let (sin_b, cos_b) = (a + angle).sin_cos();
+
+    // Uniform point in the triangle (center, a, b)
+    let su = u0.sqrt();
+    let v = u.y;
+    let (wa, wb) = (su * (1.0 - v), su * v);
+    Vec2::new(wa * cos_a + wb * cos_b, wa * sin_a + wb * sin_b)
+}
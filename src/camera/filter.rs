@@ -0,0 +1,145 @@
+use std::f32::consts::PI;
+
+use crate::math::Vec2;
+
+/// Pixel reconstruction filter weighting samples by their offset from the pixel center.
+///
+/// Samples are splatted into every pixel within the radius (in pixels), so filters wider
+/// than half a pixel blur the image slightly but reduce aliasing.
+/// Mitchell and Lanczos filters have negative lobes which sharpen edges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    Box {
+        radius: Vec2,
+    },
+    /// Weight falls off linearly to zero at the radius.
+    Tent {
+        radius: Vec2,
+    },
+    /// Gaussian shifted down to reach zero at the radius.
+    Gaussian {
+        radius: Vec2,
+        /// The standard deviation in pixels.
+        sigma: f32,
+    },
+    /// Mitchell–Netravali cubic, `b = c = 1/3` is the recommended compromise between blurring and ringing.
+    Mitchell {
+        radius: Vec2,
+        b: f32,
+        c: f32,
+    },
+    /// Sinc windowed by a wider sinc.
+    Lanczos {
+        radius: Vec2,
+        /// The number of sinc periods within the window.
+        tau: f32,
+    },
+}
+
+impl Default for Filter {
+    /// Box filter over a single pixel, which averages the samples of the pixel.
+    fn default() -> Self {
+        Self::Box {
+            radius: Vec2::splat(0.5),
+        }
+    }
+}
+
+impl Filter {
+    pub fn gaussian(radius: f32) -> Self {
+        Self::Gaussian {
+            radius: Vec2::splat(radius),
+            sigma: radius / 3.0,
+        }
+    }
+
+    pub fn mitchell(radius: f32) -> Self {
+        Self::Mitchell {
+            radius: Vec2::splat(radius),
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        }
+    }
+
+    /// Lanczos kernel with as many lobes as the `radius`.
+    pub fn lanczos(radius: f32) -> Self {
+        Self::Lanczos {
+            radius: Vec2::splat(radius),
+            tau: radius,
+        }
+    }
+
+    /// The extent of the filter in pixels along each axis.
+    pub fn radius(&self) -> Vec2 {
+        match *self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::Mitchell { radius, .. }
+            | Self::Lanczos { radius, .. } => radius,
+        }
+    }
+
+    /// Weight of a sample at offset `p` in pixels from the pixel center, zero outside the radius.
+    pub fn evaluate(&self, p: Vec2) -> f32 {
+        let radius = self.radius();
+        if p.x.abs() > radius.x || p.y.abs() > radius.y {
+            return 0.0;
+        }
+
+        match *self {
+            Self::Box { .. } => 1.0,
+            Self::Tent { radius } => (radius.x - p.x.abs()) * (radius.y - p.y.abs()),
+            Self::Gaussian { radius, sigma } => {
+                let g = |x: f32, r: f32| (gaussian(x, sigma) - gaussian(r, sigma)).max(0.0);
+                g(p.x, radius.x) * g(p.y, radius.y)
+            }
+            Self::Mitchell { radius, b, c } => {
+                mitchell(2.0 * p.x / radius.x, b, c) * mitchell(2.0 * p.y / radius.y, b, c)
+            }
+            Self::Lanczos { radius, tau } => {
+                windowed_sinc(p.x, radius.x, tau) * windowed_sinc(p.y, radius.y, tau)
+            }
+        }
+    }
+}
+
+#[inline]
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    f32::exp(-x * x / (2.0 * sigma * sigma)) / (f32::sqrt(2.0 * PI) * sigma)
+}
+
+/// Mitchell–Netravali cubic over `[-2.0, 2.0]`.
+fn mitchell(x: f32, b: f32, c: f32) -> f32 {
+    let x = x.abs();
+    let value = if x <= 1.0 {
+        (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+            + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+            + (6.0 - 2.0 * b)
+    } else if x <= 2.0 {
+        (-b - 6.0 * c) * x * x * x
+            + (6.0 * b + 30.0 * c) * x * x
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    } else {
+        0.0
+    };
+    value / 6.0
+}
+
+#[inline]
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        f32::sin(PI * x) / (PI * x)
+    }
+}
+
+#[inline]
+fn windowed_sinc(x: f32, radius: f32, tau: f32) -> f32 {
+    if x.abs() > radius {
+        return 0.0;
+    }
+    sinc(x) * sinc(x / tau)
+}
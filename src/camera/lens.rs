@@ -0,0 +1,320 @@
+use std::f32::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::material::microfacet::refract;
+use crate::math::{Vec2, Vec3};
+use crate::sampler::sample_uniform_disk;
+
+/// Spherical interface between two media of a lens system.
+///
+/// Lengths are in millimeters, as in lens prescriptions.
+#[derive(Clone, Copy, Debug)]
+pub struct LensElement {
+    /// Radius of curvature, positive when the center lies towards the film, `0.0` for the aperture stop.
+    pub curvature_radius: f32,
+    /// Distance along the optical axis to the next interface towards the film.
+    pub thickness: f32,
+    /// Index of refraction of the medium behind the interface, `0.0` or `1.0` for air.
+    pub ior: f32,
+    /// Radius of the clear aperture of the interface.
+    pub aperture_radius: f32,
+}
+
+/// Sequence of spherical lens elements in front of the film, ordered from the scene to the film.
+///
+/// Rays are traced through every interface, so vignetting, distortion and focus breathing
+/// follow from the prescription. The optical axis is `-Z` of the camera.
+#[derive(Clone, Debug)]
+pub struct LensSystem {
+    elements: Vec<LensElement>,
+    /// The height of the film in millimeters.
+    pub sensor_height: f32,
+}
+
+/// Ray in the lens space, where the film lies at `z = 0` and the lens towards `-Z`.
+#[derive(Clone, Copy, Debug)]
+struct LensRay {
+    origin: Vec3,
+    dir: Vec3,
+}
+
+impl LensRay {
+    fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.dir * t
+    }
+}
+
+impl LensSystem {
+    /// Creates the lens system with the film focused at `focus_dist` meters,
+    /// the thickness of the last element is replaced by the film distance.
+    ///
+    /// Returns `None` if the lens can not focus at that distance.
+    pub fn new(elements: Vec<LensElement>, focus_dist: f32) -> Option<Self> {
+        assert!(!elements.is_empty(), "Lens system must not be empty");
+        let mut lens_system = Self {
+            elements,
+            sensor_height: 24.0,
+        };
+        lens_system.focus(focus_dist).then_some(lens_system)
+    }
+
+    /// Loads a lens prescription focused at `focus_dist` meters, each line holds the curvature radius,
+    /// thickness, index of refraction and aperture diameter in millimeters. Lines starting with `#` are ignored.
+    pub fn load<P: AsRef<Path>>(path: P, focus_dist: f32) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut elements = Vec::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values = line
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid("Lens element values must be numbers"))?;
+            let [curvature_radius, thickness, ior, aperture_diameter] = values[..] else {
+                return Err(invalid("Lens element must have 4 values"));
+            };
+
+            elements.push(LensElement {
+                curvature_radius,
+                thickness,
+                ior,
+                aperture_radius: aperture_diameter / 2.0,
+            });
+        }
+
+        if elements.is_empty() {
+            return Err(invalid("Lens prescription has no elements"));
+        }
+        Self::new(elements, focus_dist).ok_or_else(|| invalid("Lens can not focus at the distance"))
+    }
+
+    pub fn elements(&self) -> &[LensElement] {
+        &self.elements
+    }
+
+    /// Distance of the film behind the last interface in millimeters.
+    pub fn film_distance(&self) -> f32 {
+        self.rear().thickness
+    }
+
+    /// Sets the diameter of the aperture stop in millimeters, limited by its size in the prescription.
+    pub fn set_stop_diameter(&mut self, diameter: f32) {
+        for element in self.elements.iter_mut() {
+            if element.curvature_radius == 0.0 {
+                element.aperture_radius = element.aperture_radius.min(diameter / 2.0);
+            }
+        }
+    }
+
+    /// Moves the film to focus objects at `focus_dist` meters from the film,
+    /// returns `false` if the lens can not focus at that distance.
+    pub fn focus(&mut self, focus_dist: f32) -> bool {
+        let Some(([pz_scene, pz_film], [fz_scene, _])) = self.thick_lens_approximation() else {
+            return false;
+        };
+
+        // Thick lens equation solved for the shift of the film
+        let f = fz_scene - pz_scene;
+        let z = -focus_dist * 1e3;
+        let c = (pz_film - z - pz_scene) * (pz_film - z - 4.0 * f - pz_scene);
+        if c <= 0.0 {
+            return false;
+        }
+
+        let delta = 0.5 * (pz_film - z + pz_scene - c.sqrt());
+        let film_distance = self.film_distance() + delta;
+        if film_distance <= 0.0 {
+            return false;
+        }
+
+        self.elements.last_mut().unwrap().thickness = film_distance;
+        true
+    }
+
+    /// Samples a camera space ray in meters through the film point `film`
+    /// in `[-1.0, 1.0]^2`, with `y` up and the width scaled by `aspect_ratio`.
+    /// The uniform sample `u` chooses the point on the rear element.
+    ///
+    /// Returns the origin, direction and the weight of the radiance along the ray,
+    /// which falls off with `cos^4` of its angle to the axis, or `None` if the ray is blocked inside the lens.
+    pub fn sample_ray(&self, film: Vec2, aspect_ratio: f32, u: Vec2) -> Option<(Vec3, Vec3, f32)> {
+        let half_height = self.sensor_height / 2.0;
+        // The image on the film is inverted
+        let film_point = Vec3::new(
+            -film.x * half_height * aspect_ratio,
+            -film.y * half_height,
+            0.0,
+        );
+
+        let rear = self.rear();
+        let film_distance = self.film_distance();
+        let pupil = sample_uniform_disk(u) * rear.aperture_radius;
+        let rear_point = Vec3::new(pupil.x, pupil.y, -film_distance);
+
+        let ray = LensRay {
+            origin: film_point,
+            dir: (rear_point - film_point).normalized(),
+        };
+
+        // Irradiance on the film from the rear element, sampled uniformly over its area
+        let cos_theta = -ray.dir.z;
+        let pupil_area = PI * rear.aperture_radius * rear.aperture_radius;
+        let weight = cos_theta.powi(4) * pupil_area / (film_distance * film_distance);
+
+        let ray = self.trace_from_film(ray)?;
+        Some((ray.origin * 1e-3, ray.dir, weight))
+    }
+
+    fn rear(&self) -> &LensElement {
+        self.elements.last().unwrap()
+    }
+
+    /// Distance from the film to the front interface.
+    fn front_z(&self) -> f32 {
+        self.elements.iter().map(|element| element.thickness).sum()
+    }
+
+    fn trace_from_film(&self, mut ray: LensRay) -> Option<LensRay> {
+        let mut element_z = 0.0;
+        for (i, element) in self.elements.iter().enumerate().rev() {
+            element_z -= element.thickness;
+            let eta_i = medium_ior(element.ior);
+            let eta_t = if i > 0 {
+                medium_ior(self.elements[i - 1].ior)
+            } else {
+                1.0
+            };
+            ray = Self::trace_interface(element, element_z, ray, eta_t / eta_i)?;
+        }
+        Some(ray)
+    }
+
+    fn trace_from_scene(&self, mut ray: LensRay) -> Option<LensRay> {
+        let mut element_z = -self.front_z();
+        for (i, element) in self.elements.iter().enumerate() {
+            let eta_i = if i > 0 {
+                medium_ior(self.elements[i - 1].ior)
+            } else {
+                1.0
+            };
+            let eta_t = medium_ior(element.ior);
+            ray = Self::trace_interface(element, element_z, ray, eta_t / eta_i)?;
+            element_z += element.thickness;
+        }
+        Some(ray)
+    }
+
+    /// Intersects and refracts the `ray` by the interface at `element_z` on the axis,
+    /// `eta` is the ratio of the transmitted to incident IOR.
+    fn trace_interface(
+        element: &LensElement,
+        element_z: f32,
+        ray: LensRay,
+        eta: f32,
+    ) -> Option<LensRay> {
+        let is_stop = element.curvature_radius == 0.0;
+        let (t, normal) = if is_stop {
+            if ray.dir.z == 0.0 {
+                return None;
+            }
+            ((element_z - ray.origin.z) / ray.dir.z, Vec3::Z)
+        } else {
+            intersect_spherical(element.curvature_radius, element_z, ray)?
+        };
+        if t < 0.0 {
+            return None;
+        }
+
+        let hit = ray.at(t);
+        if hit.x * hit.x + hit.y * hit.y > element.aperture_radius * element.aperture_radius {
+            return None;
+        }
+
+        if is_stop {
+            return Some(LensRay {
+                origin: hit,
+                dir: ray.dir,
+            });
+        }
+
+        let dir = refract(-ray.dir, normal, eta)?;
+        Some(LensRay { origin: hit, dir })
+    }
+
+    /// Returns the principal planes and focal points `z` for rays
+    /// entering from the scene and from the film side.
+    fn thick_lens_approximation(&self) -> Option<([f32; 2], [f32; 2])> {
+        let x = 1e-3 * self.sensor_height;
+
+        let scene_ray = LensRay {
+            origin: Vec3::new(x, 0.0, -(self.front_z() + 1.0)),
+            dir: Vec3::Z,
+        };
+        let film_out = self.trace_from_scene(scene_ray)?;
+        let (pz_scene, fz_scene) = cardinal_points(scene_ray, film_out)?;
+
+        let film_ray = LensRay {
+            origin: Vec3::new(x, 0.0, -(self.film_distance() - 1.0)),
+            dir: -Vec3::Z,
+        };
+        let scene_out = self.trace_from_film(film_ray)?;
+        let (pz_film, fz_film) = cardinal_points(film_ray, scene_out)?;
+
+        Some(([pz_scene, pz_film], [fz_scene, fz_film]))
+    }
+}
+
+/// Principal plane and focal point of a ray parallel to the axis and its refracted ray.
+fn cardinal_points(ray_in: LensRay, ray_out: LensRay) -> Option<(f32, f32)> {
+    if ray_out.dir.x == 0.0 {
+        return None;
+    }
+
+    let t_focus = -ray_out.origin.x / ray_out.dir.x;
+    let t_principal = (ray_in.origin.x - ray_out.origin.x) / ray_out.dir.x;
+    Some((ray_out.at(t_principal).z, ray_out.at(t_focus).z))
+}
+
+fn medium_ior(ior: f32) -> f32 {
+    if ior == 0.0 { 1.0 } else { ior }
+}
+
+/// Intersects the sphere of the interface with the center on the axis,
+/// returns the distance and the normal facing the ray.
+fn intersect_spherical(radius: f32, element_z: f32, ray: LensRay) -> Option<(f32, Vec3)> {
+    let z_center = element_z + radius;
+    let o = ray.origin - Vec3::new(0.0, 0.0, z_center);
+    let a = ray.dir.length_squared();
+    let b = 2.0 * ray.dir.dot(&o);
+    let c = o.length_squared() - radius * radius;
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let dsqrt = discriminant.sqrt();
+    let t0 = (-b - dsqrt) / (2.0 * a);
+    let t1 = (-b + dsqrt) / (2.0 * a);
+    // The interface is the part of the sphere facing the other side of the center
+    let use_closer = (ray.dir.z > 0.0) ^ (radius < 0.0);
+    let t = if use_closer { t0.min(t1) } else { t0.max(t1) };
+    if t < 0.0 {
+        return None;
+    }
+
+    let normal = (o + ray.dir * t).normalized();
+    let normal = if normal.dot(&ray.dir) > 0.0 {
+        -normal
+    } else {
+        normal
+    };
+    Some((t, normal))
+}
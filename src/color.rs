@@ -35,6 +35,22 @@ impl Color {
         self.0.z
     }
 
+    /// Relative luminance of the linear color (Rec. 709 primaries).
+    #[inline]
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+
+    #[inline]
+    pub fn max_component(&self) -> f32 {
+        self.r().max(self.g()).max(self.b())
+    }
+
+    #[inline]
+    pub fn is_black(&self) -> bool {
+        self.r() <= 0.0 && self.g() <= 0.0 && self.b() <= 0.0
+    }
+
     #[inline]
     pub fn linear_to_gamma(&self) -> Color {
         Color::new(
@@ -1,5 +1,6 @@
 use std::fs::File;
 use std::io;
+use std::ops::Range;
 use std::path::Path;
 
 use crate::color::Color;
@@ -46,41 +47,6 @@ impl Image {
         &self.pixels
     }
 
-    pub fn split_n(&mut self, n: u32) -> Vec<SubImage<'_>> {
-        let mut sub_images = Vec::with_capacity(n as usize);
-        let mut remaining_pixels = self.pixels.as_mut_slice();
-
-        let rows_per_stripe = self.height / n;
-        let mut remainder = self.height % n;
-        let mut current_y_offset = 0;
-
-        for _ in 0..n {
-            // Give an extra row to the first few stripes if there's a remainder
-            let stripe_height = rows_per_stripe + if remainder > 0 { 1 } else { 0 };
-            remainder = remainder.saturating_sub(1);
-
-            if stripe_height == 0 {
-                break;
-            }
-
-            let byte_count = (stripe_height * self.stride) as usize;
-            let (current_slice, rest) = remaining_pixels.split_at_mut(byte_count);
-            remaining_pixels = rest;
-
-            sub_images.push(SubImage {
-                width: self.width,
-                height: stripe_height,
-                stride: self.stride,
-                y_offset: current_y_offset,
-                pixels: current_slice,
-            });
-
-            current_y_offset += stripe_height;
-        }
-
-        sub_images
-    }
-
     pub fn save_as_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
         let mut file = File::create(path)?;
         ppm::write(&mut file, &self.pixels, self.width, self.height)
@@ -116,62 +82,295 @@ impl RenderTarget for Image {
     }
 }
 
-pub struct SubImage<'a> {
-    width: u32,
-    height: u32,
-    stride: u32,
-    y_offset: u32,
-    pixels: &'a mut [u8],
+/// Rectangle of image pixels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PixelBounds {
+    pub x: Range<u32>,
+    pub y: Range<u32>,
 }
 
-impl SubImage<'_> {
-    pub fn aspect_ratio(&self) -> f64 {
-        self.width as f64 / self.height as f64
+impl PixelBounds {
+    /// Bounds of the whole image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            x: 0..width,
+            y: 0..height,
+        }
     }
 
-    pub fn get_y_offset(&self) -> u32 {
-        self.y_offset
+    /// Grows the bounds by `margin` pixels on each side, limited to the image of `width` and `height`.
+    pub fn expanded(&self, margin: u32, width: u32, height: u32) -> Self {
+        Self {
+            x: self.x.start.saturating_sub(margin)..(self.x.end + margin).min(width),
+            y: self.y.start.saturating_sub(margin)..(self.y.end + margin).min(height),
+        }
     }
 
-    pub fn pixels(&self) -> &[u8] {
-        self.pixels
+    /// The intersection with `other`, empty if they do not overlap.
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self {
+            x: self.x.start.max(other.x.start)..self.x.end.min(other.x.end),
+            y: self.y.start.max(other.y.start)..self.y.end.min(other.y.end),
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        self.x.contains(&x) && self.y.contains(&y)
+    }
+
+    pub fn area(&self) -> usize {
+        self.x.len() * self.y.len()
+    }
+
+    /// Iterates over the pixels row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + use<> {
+        let x = self.x.clone();
+        self.y
+            .clone()
+            .flat_map(move |y| x.clone().map(move |x| (x, y)))
     }
 }
 
-impl RenderTarget for SubImage<'_> {
-    fn get_width(&self) -> u32 {
-        self.width
+/// Floating point accumulation buffer of filtered samples for a rectangle of image pixels.
+///
+/// Filters wider than a pixel splat samples into the pixels of neighboring tiles,
+/// so films of tiles overlap by the filter radius and are merged after rendering.
+#[derive(Clone, Debug)]
+pub struct Film {
+    bounds: PixelBounds,
+    pixels: Vec<FilmPixel>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FilmPixel {
+    /// Sum of the sample colors multiplied by their filter weights.
+    color_sum: Color,
+    weight_sum: f32,
+    /// The number of samples taken in the pixel, regardless of where they were splatted.
+    sample_count: u32,
+}
+
+impl FilmPixel {
+    const EMPTY: FilmPixel = FilmPixel {
+        color_sum: Color::BLACK,
+        weight_sum: 0.0,
+        sample_count: 0,
+    };
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_bounds(PixelBounds::new(width, height))
     }
 
-    fn get_height(&self) -> u32 {
-        self.height
+    /// Creates a film covering only the image pixels within the `bounds`.
+    pub fn with_bounds(bounds: PixelBounds) -> Self {
+        Self {
+            pixels: vec![FilmPixel::EMPTY; bounds.area()],
+            bounds,
+        }
     }
 
-    fn put_pixel(&mut self, x: u32, y: u32, color: Color) {
-        let gamma = color.linear_to_gamma();
+    pub fn bounds(&self) -> &PixelBounds {
+        &self.bounds
+    }
 
-        // Gamma gives the valid range [0.0, 1.0]
-        let r = (255.0 * gamma.r()) as u8;
-        let g = (255.0 * gamma.g()) as u8;
-        let b = (255.0 * gamma.b()) as u8;
+    /// Adds a sample with the filter `weight` to the image pixel `(x, y)`, ignored outside of the film.
+    #[inline]
+    pub fn add_sample(&mut self, x: u32, y: u32, color: Color, weight: f32) {
+        if let Some(index) = self.index(x, y) {
+            let pixel = &mut self.pixels[index];
+            pixel.color_sum += color * weight;
+            pixel.weight_sum += weight;
+        }
+    }
 
-        let index = y as usize * self.stride as usize + x as usize * 3;
-        self.pixels[index] = r;
-        self.pixels[index + 1] = g;
-        self.pixels[index + 2] = b;
+    /// Counts `count` samples taken in the image pixel `(x, y)`, ignored outside of the film.
+    pub fn add_sample_count(&mut self, x: u32, y: u32, count: u32) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index].sample_count += count;
+        }
     }
 
-    fn coordinate(&self, x: u32, y: u32) -> (u32, u32) {
-        (x, y + self.y_offset)
+    /// The number of samples taken in the image pixel `(x, y)`.
+    pub fn sample_count(&self, x: u32, y: u32) -> u32 {
+        self.index(x, y)
+            .map_or(0, |index| self.pixels[index].sample_count)
+    }
+
+    /// Adds the samples of the pixels covered by both films.
+    pub fn merge(&mut self, other: &Film) {
+        for (x, y) in self.bounds.intersect(&other.bounds).pixels() {
+            let (Some(index), Some(other_index)) = (self.index(x, y), other.index(x, y)) else {
+                continue;
+            };
+            let other_pixel = other.pixels[other_index];
+            let pixel = &mut self.pixels[index];
+            pixel.color_sum += other_pixel.color_sum;
+            pixel.weight_sum += other_pixel.weight_sum;
+            pixel.sample_count += other_pixel.sample_count;
+        }
     }
+
+    /// Reconstructed color of the image pixel `(x, y)`, black if it has no samples.
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let Some(index) = self.index(x, y) else {
+            return Color::BLACK;
+        };
+
+        let pixel = self.pixels[index];
+        // Negative filter lobes may cancel out the weights
+        if pixel.weight_sum.abs() <= f32::EPSILON {
+            Color::BLACK
+        } else {
+            pixel.color_sum * pixel.weight_sum.recip()
+        }
+    }
+
+    /// Writes the reconstructed pixels covered by both the film and the `target`.
+    pub fn write_to<R: RenderTarget>(&self, target: &mut R) {
+        for y in 0..target.get_height() {
+            for x in 0..target.get_width() {
+                let (ix, iy) = target.coordinate(x, y);
+                if self.bounds.contains(ix, iy) {
+                    target.put_pixel(x, y, self.pixel(ix, iy));
+                }
+            }
+        }
+    }
+
+    /// Writes a heatmap of the sample counts covered by both the film and the `target`,
+    /// from blue at `min_samples` to red at `max_samples`.
+    pub fn write_sample_heatmap<R: RenderTarget>(
+        &self,
+        target: &mut R,
+        min_samples: u32,
+        max_samples: u32,
+    ) {
+        let range = max_samples.saturating_sub(min_samples).max(1) as f32;
+        for y in 0..target.get_height() {
+            for x in 0..target.get_width() {
+                let (ix, iy) = target.coordinate(x, y);
+                if self.bounds.contains(ix, iy) {
+                    let count = self.sample_count(ix, iy).saturating_sub(min_samples);
+                    target.put_pixel(x, y, heatmap(count as f32 / range));
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        self.bounds.contains(x, y).then(|| {
+            (y - self.bounds.y.start) as usize * self.bounds.x.len()
+                + (x - self.bounds.x.start) as usize
+        })
+    }
+}
+
+/// Maps `t` in `[0.0, 1.0]` to blue, cyan, green, yellow and red.
+fn heatmap(t: f32) -> Color {
+    const COLORS: [Color; 5] = [
+        Color::new(0.0, 0.0, 1.0),
+        Color::new(0.0, 1.0, 1.0),
+        Color::new(0.0, 1.0, 0.0),
+        Color::new(1.0, 1.0, 0.0),
+        Color::new(1.0, 0.0, 0.0),
+    ];
+
+    let t = t.clamp(0.0, 1.0) * (COLORS.len() - 1) as f32;
+    let i = (t as usize).min(COLORS.len() - 2);
+    COLORS[i].lerp(COLORS[i + 1], t - i as f32)
 }
 
 pub mod ppm {
     use std::io;
 
     const MAGIC: &[u8] = b"P6";
+    const ASCII_MAGIC: &[u8] = b"P3";
     const MAX_PIXEL_VALUE: u16 = 255;
 
+    /// Reads a binary (`P6`) or ASCII (`P3`) PPM image.
+    ///
+    /// Returns the width, height and RGB pixels scaled to 8 bits.
+    pub fn read<R: io::Read>(reader: &mut R) -> Result<(u32, u32, Vec<u8>), io::Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let mut cursor = 0;
+        let magic = next_token(&data, &mut cursor)?;
+        if magic != MAGIC && magic != ASCII_MAGIC {
+            return Err(invalid_data("PPM requires P6 or P3 magic"));
+        }
+        let is_binary = magic == MAGIC;
+
+        let width = parse_number(next_token(&data, &mut cursor)?)?;
+        let height = parse_number(next_token(&data, &mut cursor)?)?;
+        let max_value = parse_number(next_token(&data, &mut cursor)?)?;
+        if max_value == 0 || max_value > u16::MAX as u32 {
+            return Err(invalid_data("PPM maximum value is out of range"));
+        }
+
+        let len = width as usize * height as usize * 3;
+        let mut pixels = Vec::with_capacity(len);
+        let scale = |value: u32| (value.min(max_value) * MAX_PIXEL_VALUE as u32 / max_value) as u8;
+
+        if is_binary {
+            // Single whitespace separates the header from the raster
+            let raster = data.get(cursor + 1..).unwrap_or_default();
+            let bytes_per_value = if max_value > 255 { 2 } else { 1 };
+            if raster.len() < len * bytes_per_value {
+                return Err(invalid_data("PPM raster is truncated"));
+            }
+            for value in raster.chunks_exact(bytes_per_value).take(len) {
+                let value = value.iter().fold(0, |acc, &byte| (acc << 8) | byte as u32);
+                pixels.push(scale(value));
+            }
+        } else {
+            for _ in 0..len {
+                pixels.push(scale(parse_number(next_token(&data, &mut cursor)?)?));
+            }
+        }
+
+        Ok((width, height, pixels))
+    }
+
+    fn next_token<'a>(data: &'a [u8], cursor: &mut usize) -> Result<&'a [u8], io::Error> {
+        loop {
+            match data.get(*cursor) {
+                Some(b'#') => {
+                    while data.get(*cursor).is_some_and(|&byte| byte != b'\n') {
+                        *cursor += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => *cursor += 1,
+                Some(_) => break,
+                None => return Err(invalid_data("PPM header is truncated")),
+            }
+        }
+
+        let start = *cursor;
+        while data
+            .get(*cursor)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            *cursor += 1;
+        }
+        Ok(&data[start..*cursor])
+    }
+
+    fn parse_number(token: &[u8]) -> Result<u32, io::Error> {
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| invalid_data("PPM contains invalid number"))
+    }
+
+    fn invalid_data(message: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+
     pub fn write<W: io::Write>(
         writer: &mut W,
         pixels: &[u8],
@@ -190,3 +389,150 @@ pub mod ppm {
         writer.write_all(pixels)
     }
 }
+
+pub mod hdr {
+    use std::io;
+
+    const MAGIC: &[&[u8]] = &[b"#?RADIANCE", b"#?RGBE"];
+    const FORMAT: &[u8] = b"FORMAT=32-bit_rle_rgbe";
+
+    /// Reads a Radiance RGBE (`.hdr`) image with `-Y height +X width` orientation.
+    ///
+    /// Returns the width, height and linear RGB values.
+    pub fn read<R: io::Read>(reader: &mut R) -> Result<(u32, u32, Vec<f32>), io::Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut cursor = 0;
+
+        let magic = next_line(&data, &mut cursor)?;
+        if !MAGIC.contains(&magic) {
+            return Err(invalid_data("HDR requires #?RADIANCE magic"));
+        }
+
+        loop {
+            let line = next_line(&data, &mut cursor)?;
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with(b"FORMAT=") && line != FORMAT {
+                return Err(invalid_data("HDR supports only RGBE format"));
+            }
+        }
+
+        let resolution = std::str::from_utf8(next_line(&data, &mut cursor)?)
+            .map_err(|_| invalid_data("HDR resolution is invalid"))?;
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", height, "+X", width] => (parse_number(height)?, parse_number(width)?),
+            _ => return Err(invalid_data("HDR supports only -Y H +X W orientation")),
+        };
+
+        let mut rgbe = vec![0u8; width as usize * 4];
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+        for _ in 0..height {
+            read_scanline(&data, &mut cursor, &mut rgbe)?;
+            for texel in rgbe.chunks_exact(4) {
+                let [r, g, b] = rgbe_to_rgb(texel);
+                pixels.extend_from_slice(&[r, g, b]);
+            }
+        }
+
+        Ok((width, height, pixels))
+    }
+
+    fn read_scanline(data: &[u8], cursor: &mut usize, rgbe: &mut [u8]) -> Result<(), io::Error> {
+        let width = rgbe.len() / 4;
+        let header = data
+            .get(*cursor..*cursor + 4)
+            .ok_or_else(|| invalid_data("HDR raster is truncated"))?;
+
+        let is_rle = (8..0x8000).contains(&width)
+            && header[0] == 2
+            && header[1] == 2
+            && ((header[2] as usize) << 8 | header[3] as usize) == width;
+        if !is_rle {
+            let flat = data
+                .get(*cursor..*cursor + rgbe.len())
+                .ok_or_else(|| invalid_data("HDR raster is truncated"))?;
+            rgbe.copy_from_slice(flat);
+            *cursor += rgbe.len();
+            return Ok(());
+        }
+
+        *cursor += 4;
+        // Each channel is run length encoded separately
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = *data
+                    .get(*cursor)
+                    .ok_or_else(|| invalid_data("HDR raster is truncated"))?
+                    as usize;
+                *cursor += 1;
+
+                if count > 128 {
+                    let count = count - 128;
+                    let value = *data
+                        .get(*cursor)
+                        .ok_or_else(|| invalid_data("HDR raster is truncated"))?;
+                    *cursor += 1;
+                    if count == 0 || x + count > width {
+                        return Err(invalid_data("HDR run length is invalid"));
+                    }
+                    for _ in 0..count {
+                        rgbe[x * 4 + channel] = value;
+                        x += 1;
+                    }
+                } else {
+                    if count == 0 || x + count > width {
+                        return Err(invalid_data("HDR run length is invalid"));
+                    }
+                    let values = data
+                        .get(*cursor..*cursor + count)
+                        .ok_or_else(|| invalid_data("HDR raster is truncated"))?;
+                    *cursor += count;
+                    for &value in values {
+                        rgbe[x * 4 + channel] = value;
+                        x += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rgbe_to_rgb(rgbe: &[u8]) -> [f32; 3] {
+        if rgbe[3] == 0 {
+            return [0.0; 3];
+        }
+        // 2^(e - 128) / 256
+        let scale = f32::powi(2.0, rgbe[3] as i32 - 136);
+        [
+            (rgbe[0] as f32 + 0.5) * scale,
+            (rgbe[1] as f32 + 0.5) * scale,
+            (rgbe[2] as f32 + 0.5) * scale,
+        ]
+    }
+
+    fn next_line<'a>(data: &'a [u8], cursor: &mut usize) -> Result<&'a [u8], io::Error> {
+        let rest = data
+            .get(*cursor..)
+            .ok_or_else(|| invalid_data("HDR header is truncated"))?;
+        let len = rest
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or_else(|| invalid_data("HDR header is truncated"))?;
+        *cursor += len + 1;
+        Ok(&rest[..len])
+    }
+
+    fn parse_number(token: &str) -> Result<u32, io::Error> {
+        token
+            .parse()
+            .map_err(|_| invalid_data("HDR contains invalid number"))
+    }
+
+    fn invalid_data(message: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+}
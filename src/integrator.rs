@@ -0,0 +1,205 @@
+use crate::color::Color;
+use crate::math::{Interval, Vec3};
+use crate::primitives::{HitRecord, Ray};
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+
+/// Unidirectional path tracer with next-event estimation.
+///
+/// Lights that can also be hit by rays are combined with BSDF sampling
+/// by multiple importance sampling.
+/// Dim paths are terminated early by Russian roulette.
+#[derive(Clone, Copy, Debug)]
+pub struct PathIntegrator {
+    /// The maximum number of bounces of a path.
+    pub max_depth: u32,
+    /// Number of bounces before Russian roulette starts, `None` disables it.
+    pub rr_depth: Option<u32>,
+}
+
+impl Default for PathIntegrator {
+    fn default() -> Self {
+        Self::new(50)
+    }
+}
+
+impl PathIntegrator {
+    /// Ignores intersections closer than this to the ray origin.
+    const T_MIN: f32 = 0.001;
+    /// Shortens shadow rays to not hit the light itself.
+    const SHADOW_EPSILON: f32 = 1e-3;
+
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+            rr_depth: Some(3),
+        }
+    }
+
+    /// Estimates radiance arriving along the `ray`.
+    ///
+    /// Every bounce takes the same dimensions from the `sampler`: light selection,
+    /// light sample, BSDF lobe, BSDF direction and Russian roulette.
+    pub fn radiance(&self, scene: &Scene, ray: Ray, sampler: &mut dyn Sampler) -> Color {
+        let mut radiance = Color::BLACK;
+        let mut throughput = Color::WHITE;
+        let mut ray = ray;
+        // Camera rays can not be sampled by lights, same as specular bounces
+        let mut prev = PathVertex {
+            point: ray.origin(),
+            normal: Vec3::ZERO,
+            is_specular: true,
+            bsdf_pdf: 0.0,
+        };
+
+        for depth in 0..self.max_depth {
+            let Some(hit) = scene.hit(&ray, Interval::new(Self::T_MIN, ray.t_max())) else {
+                radiance += throughput * self.escaped(scene, &ray, &prev);
+                break;
+            };
+
+            let wo = -ray.direction().normalized();
+            let emitted = hit.material.emitted(&hit);
+            if !emitted.is_black() {
+                radiance += throughput * emitted * self.emission_weight(scene, &ray, &hit, &prev);
+            }
+            radiance += throughput * self.sample_light(scene, &hit, wo, sampler);
+
+            let uc = sampler.get_1d();
+            let u = sampler.get_2d();
+            let u_rr = sampler.get_1d();
+            let Some(sample) = hit.material.sample(wo, &hit, uc, u) else {
+                break;
+            };
+            if sample.pdf <= 0.0 {
+                break;
+            }
+
+            let cos_theta = sample.wi.normalized().dot(&hit.normal).abs();
+            throughput = throughput * sample.f * (cos_theta / sample.pdf);
+            if throughput.is_black() {
+                break;
+            }
+
+            // Survivors are reweighted so the estimate stays unbiased
+            if self.rr_depth.is_some_and(|rr_depth| depth >= rr_depth) {
+                let survival = throughput.max_component().min(1.0);
+                if u_rr >= survival {
+                    break;
+                }
+                throughput *= survival.recip();
+            }
+
+            prev = PathVertex {
+                point: hit.point,
+                normal: hit.normal,
+                is_specular: sample.is_specular,
+                bsdf_pdf: sample.pdf,
+            };
+            ray = hit.spawn_ray(sample.wi);
+        }
+
+        radiance
+    }
+
+    /// Radiance of lights at infinity arriving along the escaped `ray`.
+    fn escaped(&self, scene: &Scene, ray: &Ray, prev: &PathVertex) -> Color {
+        let mut radiance = scene.background;
+        for (index, light) in scene.infinite_lights() {
+            let le = light.le(ray);
+            if le.is_black() {
+                continue;
+            }
+
+            let weight = if prev.is_specular {
+                1.0
+            } else {
+                let light_pmf = scene.light_sampler().pmf(prev.point, prev.normal, index);
+                let light_pdf = light_pmf * light.pdf_li(prev.point, ray.direction());
+                power_heuristic(prev.bsdf_pdf, light_pdf)
+            };
+            radiance += le * weight;
+        }
+        radiance
+    }
+
+    /// Multiple importance sampling weight of emission hit by BSDF sampling.
+    fn emission_weight(&self, scene: &Scene, ray: &Ray, hit: &HitRecord, prev: &PathVertex) -> f32 {
+        if prev.is_specular {
+            return 1.0;
+        }
+        // Emitters which are not lights can only be found by BSDF sampling
+        let Some(index) = hit.light.and_then(|light| scene.light_index(light)) else {
+            return 1.0;
+        };
+
+        let light = &scene.lights()[index];
+        let light_pmf = scene.light_sampler().pmf(prev.point, prev.normal, index);
+        let light_pdf = light_pmf * light.pdf_li(prev.point, ray.direction());
+        power_heuristic(prev.bsdf_pdf, light_pdf)
+    }
+
+    /// Estimates direct illumination from a light chosen by the scene light sampler.
+    fn sample_light(
+        &self,
+        scene: &Scene,
+        hit: &HitRecord,
+        wo: Vec3,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let u_light = sampler.get_1d();
+        let u = sampler.get_2d();
+        let Some(sampled) = scene.light_sampler().sample(hit.point, hit.normal, u_light) else {
+            return Color::BLACK;
+        };
+        if sampled.pmf <= 0.0 {
+            return Color::BLACK;
+        }
+
+        let light = &scene.lights()[sampled.index];
+        let Some(light_sample) = light.sample_li(hit.point, u) else {
+            return Color::BLACK;
+        };
+        if light_sample.pdf <= 0.0 || light_sample.radiance.is_black() {
+            return Color::BLACK;
+        }
+
+        let f = hit.material.eval(wo, light_sample.wi, hit);
+        let cos_theta = light_sample.wi.dot(&hit.normal).abs();
+        if f.is_black() || cos_theta == 0.0 {
+            return Color::BLACK;
+        }
+
+        let shadow_ray = hit.spawn_ray(light_sample.wi);
+        let t_max = light_sample.distance * (1.0 - Self::SHADOW_EPSILON);
+        if scene.is_occluded(&shadow_ray, Interval::new(Self::T_MIN, t_max)) {
+            return Color::BLACK;
+        }
+
+        let light_pdf = sampled.pmf * light_sample.pdf;
+        let weight = if light.is_delta() {
+            1.0
+        } else {
+            power_heuristic(light_pdf, hit.material.pdf(wo, light_sample.wi, hit))
+        };
+
+        f * light_sample.radiance * (cos_theta * weight / light_pdf)
+    }
+}
+
+/// Previous vertex of a path, needed to weight emission found by BSDF sampling.
+struct PathVertex {
+    point: Vec3,
+    normal: Vec3,
+    /// Whether the direction leaving the vertex was sampled from a specular lobe.
+    is_specular: bool,
+    bsdf_pdf: f32,
+}
+
+/// Multiple importance sampling weight of the strategy with density `pdf_a`.
+#[inline]
+fn power_heuristic(pdf_a: f32, pdf_b: f32) -> f32 {
+    let a = pdf_a * pdf_a;
+    let b = pdf_b * pdf_b;
+    if a + b == 0.0 { 0.0 } else { a / (a + b) }
+}
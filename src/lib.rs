@@ -1,7 +1,12 @@
 pub mod camera;
 pub mod color;
 pub mod image;
+pub mod integrator;
+pub mod light;
 pub mod material;
 pub mod math;
 pub mod primitives;
 pub mod renderer;
+pub mod sampler;
+pub mod scene;
+pub mod texture;
@@ -0,0 +1,220 @@
+mod environment;
+mod sampler;
+mod sky;
+mod sphere;
+
+pub use environment::*;
+pub use sampler::*;
+pub use sky::*;
+pub use sphere::*;
+
+use std::f32::consts::PI;
+use std::fmt;
+
+use crate::color::Color;
+use crate::math::{Aabb, Vec2, Vec3};
+use crate::primitives::Ray;
+
+/// Source of direct illumination sampled through next-event estimation.
+pub trait Light: fmt::Debug {
+    /// Samples incident illumination arriving at `point` with the uniform sample `u`.
+    fn sample_li(&self, point: Vec3, u: Vec2) -> Option<LightSample>;
+
+    /// Returns the solid angle density of sampling `wi` at `point` by [`Light::sample_li`].
+    fn pdf_li(&self, _point: Vec3, _wi: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Returns radiance carried by a ray escaping the scene, only lights at infinity emit it.
+    fn le(&self, _ray: &Ray) -> Color {
+        Color::BLACK
+    }
+
+    /// Returns the total emitted power.
+    ///
+    /// Lights at infinity return the power falling onto a scene bounded by `scene_radius`.
+    fn power(&self, scene_radius: f32) -> Color;
+
+    /// Returns the spatial and directional bounds of the emission, `None` for lights at infinity.
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+
+    /// Whether the light is described by a delta distribution and can not be hit by rays.
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// Whether the light surrounds the scene at infinity.
+    fn is_infinite(&self) -> bool {
+        false
+    }
+}
+
+/// Illumination arriving from a light.
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+    /// Unit direction from the shaded point towards the light.
+    pub wi: Vec3,
+    /// Incident radiance, already divided by the squared distance for point-like lights.
+    pub radiance: Color,
+    /// Distance to the light, infinite for lights at infinity.
+    pub distance: f32,
+    /// Solid angle density of the sampled direction, `1.0` for delta lights.
+    pub pdf: f32,
+}
+
+/// Isotropic point light.
+#[derive(Clone, Copy, Debug)]
+pub struct PointLight {
+    pub position: Vec3,
+    /// Radiant intensity in all directions.
+    pub intensity: Color,
+}
+
+/// Point light emitting into a cone.
+#[derive(Clone, Copy, Debug)]
+pub struct SpotLight {
+    pub position: Vec3,
+    /// Direction of the cone axis.
+    pub direction: Vec3,
+    /// Radiant intensity along the cone axis.
+    pub intensity: Color,
+    /// Half angle of the cone in radians, no light is emitted outside of it.
+    pub cone_angle: f32,
+    /// Half angle in radians where the intensity starts to fall off towards the cone edge.
+    pub falloff_angle: f32,
+}
+
+/// Light at infinity illuminating the scene from a single direction, like the sun.
+#[derive(Clone, Copy, Debug)]
+pub struct DirectionalLight {
+    /// Direction the light travels in.
+    pub direction: Vec3,
+    /// Irradiance on a surface perpendicular to the light.
+    pub irradiance: Color,
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, point: Vec3, _u: Vec2) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            wi: to_light / distance,
+            radiance: self.intensity * distance_squared.recip(),
+            distance,
+            pdf: 1.0,
+        })
+    }
+
+    fn power(&self, _scene_radius: f32) -> Color {
+        self.intensity * (4.0 * PI)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds {
+            bounds: Aabb::from_corners(self.position, self.position),
+            direction: Vec3::Z,
+            phi: self.intensity.luminance() * 4.0 * PI,
+            cos_theta_o: -1.0,
+            cos_theta_e: 0.0,
+            two_sided: false,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+impl SpotLight {
+    /// Smooth falloff of the intensity between the falloff and the cone angles.
+    fn falloff(&self, cos_theta: f32) -> f32 {
+        let cos_cone = self.cone_angle.cos();
+        let cos_falloff = self.falloff_angle.min(self.cone_angle).cos();
+        if cos_theta >= cos_falloff {
+            return 1.0;
+        }
+        if cos_theta <= cos_cone {
+            return 0.0;
+        }
+
+        let t = (cos_theta - cos_cone) / (cos_falloff - cos_cone);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, point: Vec3, _u: Vec2) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let wi = to_light / distance;
+        let falloff = self.falloff(-wi.dot(&self.direction.normalized()));
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            wi,
+            radiance: self.intensity * (falloff / distance_squared),
+            distance,
+            pdf: 1.0,
+        })
+    }
+
+    fn power(&self, _scene_radius: f32) -> Color {
+        // Falloff is approximated as linear in the cosine
+        let cos_cone = self.cone_angle.cos();
+        let cos_falloff = self.falloff_angle.min(self.cone_angle).cos();
+        self.intensity * (2.0 * PI * (1.0 - 0.5 * (cos_cone + cos_falloff)))
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let falloff_angle = self.falloff_angle.min(self.cone_angle);
+        Some(LightBounds {
+            bounds: Aabb::from_corners(self.position, self.position),
+            direction: self.direction.normalized(),
+            phi: self.intensity.luminance() * 4.0 * PI,
+            cos_theta_o: falloff_angle.cos(),
+            cos_theta_e: f32::cos(self.cone_angle - falloff_angle),
+            two_sided: false,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _point: Vec3, _u: Vec2) -> Option<LightSample> {
+        Some(LightSample {
+            wi: -self.direction.normalized(),
+            radiance: self.irradiance,
+            distance: f32::INFINITY,
+            pdf: 1.0,
+        })
+    }
+
+    fn power(&self, scene_radius: f32) -> Color {
+        self.irradiance * (PI * scene_radius * scene_radius)
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,131 @@
+use std::f32::consts::PI;
+use std::io;
+use std::path::Path;
+
+use crate::color::Color;
+use crate::light::{Light, LightSample};
+use crate::math::{Distribution2D, Mat3, Vec2, Vec3};
+use crate::primitives::Ray;
+use crate::texture::Texture;
+
+/// Distant lighting from an equirectangular environment map.
+///
+/// The center of the map faces `-Z` and the top row is `+Y`.
+/// Directions are importance sampled proportionally to the map luminance.
+#[derive(Clone, Debug)]
+pub struct EnvironmentLight {
+    texture: Texture,
+    distribution: Distribution2D,
+    /// Rotation of the map in the world.
+    pub rotation: Mat3,
+    /// Multiplier of the map radiance.
+    pub intensity: f32,
+}
+
+impl EnvironmentLight {
+    pub fn new(texture: Texture) -> Self {
+        let width = texture.get_width() as usize;
+        let height = texture.get_height() as usize;
+
+        // Rows near the poles cover less solid angle
+        let mut func = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = f32::sin(PI * (y as f32 + 0.5) / height as f32);
+            for x in 0..width {
+                let texel = texture.texel(x as u32, y as u32);
+                func.push(texel.luminance().max(0.0) * sin_theta);
+            }
+        }
+
+        Self {
+            distribution: Distribution2D::new(&func, width, height),
+            texture,
+            rotation: Mat3::IDENTITY,
+            intensity: 1.0,
+        }
+    }
+
+    pub fn load_hdr<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Texture::load_hdr(path).map(Self::new)
+    }
+
+    /// Radiance arriving from the world space direction `dir`.
+    pub fn radiance(&self, dir: Vec3) -> Color {
+        let local = self.rotation.transpose() * dir.normalized();
+        let (u, v) = direction_to_uv(local);
+        self.texture.sample_spherical(Vec2::new(u, 1.0 - v)) * self.intensity
+    }
+}
+
+impl Light for EnvironmentLight {
+    fn sample_li(&self, _point: Vec3, u: Vec2) -> Option<LightSample> {
+        let ((u, v), uv_pdf) = self.distribution.sample_continuous(u.x, u.y);
+        if uv_pdf <= 0.0 {
+            return None;
+        }
+
+        let (local, sin_theta) = uv_to_direction(u, v);
+        if sin_theta <= 0.0 {
+            return None;
+        }
+
+        let wi = self.rotation * local;
+        Some(LightSample {
+            wi,
+            radiance: self.radiance(wi),
+            distance: f32::INFINITY,
+            pdf: uv_pdf / (2.0 * PI * PI * sin_theta),
+        })
+    }
+
+    fn pdf_li(&self, _point: Vec3, wi: Vec3) -> f32 {
+        let local = self.rotation.transpose() * wi.normalized();
+        let (u, v) = direction_to_uv(local);
+        let sin_theta = f32::sin(v * PI);
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn le(&self, ray: &Ray) -> Color {
+        self.radiance(ray.direction())
+    }
+
+    fn power(&self, scene_radius: f32) -> Color {
+        let width = self.texture.get_width();
+        let height = self.texture.get_height();
+
+        let mut sum = Color::BLACK;
+        for y in 0..height {
+            let sin_theta = f32::sin(PI * (y as f32 + 0.5) / height as f32);
+            for x in 0..width {
+                sum += self.texture.texel(x, y) * sin_theta;
+            }
+        }
+
+        // Radiance integrated over the sphere falling onto the disk of the scene
+        let solid_angle = 2.0 * PI * PI / (width as f32 * height as f32);
+        sum * (solid_angle * self.intensity * PI * scene_radius * scene_radius)
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
+
+/// Maps a local direction to the map coordinates, `v` goes from the top row down.
+pub(super) fn direction_to_uv(dir: Vec3) -> (f32, f32) {
+    let theta = dir.y.clamp(-1.0, 1.0).acos();
+    let phi = f32::atan2(-dir.x, dir.z);
+    let phi = if phi < 0.0 { phi + 2.0 * PI } else { phi };
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// Inverse of [`direction_to_uv`], also returns `sin(theta)`.
+pub(super) fn uv_to_direction(u: f32, v: f32) -> (Vec3, f32) {
+    let (sin_theta, cos_theta) = f32::sin_cos(v * PI);
+    let (sin_phi, cos_phi) = f32::sin_cos(u * 2.0 * PI);
+    let dir = Vec3::new(-sin_theta * sin_phi, cos_theta, sin_theta * cos_phi);
+    (dir, sin_theta)
+}
@@ -0,0 +1,476 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::light::Light;
+use crate::math::{Aabb, AliasTable, Vec3};
+
+/// Strategy for choosing the light sampled by next-event estimation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LightSampling {
+    /// Every light is equally likely.
+    Uniform,
+    /// Lights are chosen proportionally to their power.
+    Power,
+    /// Lights are chosen by their estimated contribution to the shaded point.
+    #[default]
+    Bvh,
+}
+
+/// Chooses one of the scene lights for a shaded point.
+///
+/// Lights are identified by their index in the slice the sampler was built from.
+pub trait LightSampler {
+    /// Chooses a light illuminating `point` on a surface with `normal`.
+    ///
+    /// `normal` may be zero when the point is not on a surface.
+    fn sample(&self, point: Vec3, normal: Vec3, u: f32) -> Option<SampledLight>;
+
+    /// Returns the probability of choosing the light `index` by [`LightSampler::sample`].
+    fn pmf(&self, point: Vec3, normal: Vec3, index: usize) -> f32;
+}
+
+/// Light chosen by a [`LightSampler`].
+#[derive(Clone, Copy, Debug)]
+pub struct SampledLight {
+    pub index: usize,
+    /// Probability of choosing the light.
+    pub pmf: f32,
+}
+
+/// Spatial and directional bounds of the emission of a light or a group of lights.
+#[derive(Clone, Copy, Debug)]
+pub struct LightBounds {
+    /// Bounds of the emitting points.
+    pub bounds: Aabb,
+    /// Axis of the cone bounding the surface normals or emission directions.
+    pub direction: Vec3,
+    /// Power emitted if the maximal intensity was emitted in all directions.
+    pub phi: f32,
+    /// Cosine of the half angle of the normal cone around `direction`.
+    pub cos_theta_o: f32,
+    /// Cosine of the angle beyond the normal cone where emission falls to zero.
+    pub cos_theta_e: f32,
+    /// Whether both sides of the surfaces emit.
+    pub two_sided: bool,
+}
+
+impl LightBounds {
+    /// Bounds enclosing both `self` and `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        if self.phi == 0.0 {
+            return *other;
+        }
+        if other.phi == 0.0 {
+            return *self;
+        }
+
+        let (direction, cos_theta_o) = cone_union(
+            self.direction,
+            self.cos_theta_o,
+            other.direction,
+            other.cos_theta_o,
+        );
+        Self {
+            bounds: self.bounds.enclose(other.bounds),
+            direction,
+            phi: self.phi + other.phi,
+            cos_theta_o,
+            cos_theta_e: self.cos_theta_e.min(other.cos_theta_e),
+            two_sided: self.two_sided || other.two_sided,
+        }
+    }
+
+    /// Conservative estimate of the contribution to `point` on a surface with `normal`.
+    pub fn importance(&self, point: Vec3, normal: Vec3) -> f32 {
+        let center = self.bounds.center();
+        let to_point = point - center;
+        if to_point.length_squared() == 0.0 {
+            return self.phi;
+        }
+        let wi = to_point.normalized();
+        // Points close to or inside the bounds are not overestimated
+        let distance_squared = to_point
+            .length_squared()
+            .max(self.bounds.diagonal().length() * 0.5);
+
+        let mut cos_theta_w = self.direction.dot(&wi);
+        if self.two_sided {
+            cos_theta_w = cos_theta_w.abs();
+        }
+        let sin_theta_w = safe_sqrt(1.0 - cos_theta_w * cos_theta_w);
+
+        // Cone of directions from the point towards the bounds
+        let (sin_theta_b, cos_theta_b) = self.subtended(point);
+        let sin_theta_o = safe_sqrt(1.0 - self.cos_theta_o * self.cos_theta_o);
+
+        // Angle between the emission cone and the direction towards the point, reduced by both cones
+        let cos_theta_x = cos_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let sin_theta_x = sin_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let cos_theta_p = cos_sub_clamped(sin_theta_x, cos_theta_x, sin_theta_b, cos_theta_b);
+        if cos_theta_p <= self.cos_theta_e {
+            return 0.0;
+        }
+
+        let mut importance = self.phi * cos_theta_p / distance_squared;
+        if normal != Vec3::ZERO {
+            let cos_theta_i = wi.dot(&normal).abs();
+            let sin_theta_i = safe_sqrt(1.0 - cos_theta_i * cos_theta_i);
+            importance *= cos_sub_clamped(sin_theta_i, cos_theta_i, sin_theta_b, cos_theta_b);
+        }
+        importance.max(0.0)
+    }
+
+    /// Returns `(sin, cos)` of the half angle of the cone bounding the box seen from `point`.
+    fn subtended(&self, point: Vec3) -> (f32, f32) {
+        if self.bounds.contains(point) {
+            return (0.0, -1.0);
+        }
+
+        let radius_squared = (self.bounds.diagonal() * 0.5).length_squared();
+        let distance_squared = (point - self.bounds.center()).length_squared();
+        if distance_squared < radius_squared {
+            return (0.0, -1.0);
+        }
+
+        let sin2_max = radius_squared / distance_squared;
+        (sin2_max.sqrt(), safe_sqrt(1.0 - sin2_max))
+    }
+}
+
+/// Chooses every light with the same probability.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformLightSampler {
+    len: usize,
+}
+
+impl UniformLightSampler {
+    pub fn new(lights: &[Arc<dyn Light + Send + Sync>]) -> Self {
+        Self { len: lights.len() }
+    }
+}
+
+impl LightSampler for UniformLightSampler {
+    fn sample(&self, _point: Vec3, _normal: Vec3, u: f32) -> Option<SampledLight> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let index = ((u * self.len as f32) as usize).min(self.len - 1);
+        Some(SampledLight {
+            index,
+            pmf: (self.len as f32).recip(),
+        })
+    }
+
+    fn pmf(&self, _point: Vec3, _normal: Vec3, index: usize) -> f32 {
+        if index < self.len {
+            (self.len as f32).recip()
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Chooses lights proportionally to their power, ignoring the shaded point.
+#[derive(Clone, Debug)]
+pub struct PowerLightSampler {
+    table: Option<AliasTable>,
+}
+
+impl PowerLightSampler {
+    /// Creates the sampler, `scene_radius` bounds the scene lit by lights at infinity.
+    pub fn new(lights: &[Arc<dyn Light + Send + Sync>], scene_radius: f32) -> Self {
+        if lights.is_empty() {
+            return Self { table: None };
+        }
+
+        let powers: Vec<f32> = lights
+            .iter()
+            .map(|light| light.power(scene_radius).luminance().max(0.0))
+            .collect();
+        Self {
+            table: Some(AliasTable::new(&powers)),
+        }
+    }
+}
+
+impl LightSampler for PowerLightSampler {
+    fn sample(&self, _point: Vec3, _normal: Vec3, u: f32) -> Option<SampledLight> {
+        let (index, pmf) = self.table.as_ref()?.sample(u);
+        Some(SampledLight { index, pmf })
+    }
+
+    fn pmf(&self, _point: Vec3, _normal: Vec3, index: usize) -> f32 {
+        self.table
+            .as_ref()
+            .filter(|table| index < table.len())
+            .map_or(0.0, |table| table.pmf(index))
+    }
+}
+
+/// Chooses lights by traversing a hierarchy of their bounds,
+/// picking the child with a higher estimated contribution more likely.
+///
+/// Lights at infinity are not bounded and are sampled uniformly alongside the hierarchy.
+#[derive(Clone, Debug)]
+pub struct BvhLightSampler {
+    nodes: Vec<LightNode>,
+    infinite_lights: Vec<usize>,
+    /// Location of each light in the sampler.
+    locations: Vec<LightLocation>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct LightNode {
+    bounds: LightBounds,
+    kind: LightNodeKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum LightNodeKind {
+    Leaf {
+        light: usize,
+    },
+    /// The first child directly follows its parent.
+    Interior {
+        second_child: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+enum LightLocation {
+    /// Light emits nothing and is never chosen.
+    None,
+    Infinite,
+    /// Bits of the path from the root, `1` for the second child, starting from the lowest bit.
+    Bvh {
+        trail: u64,
+    },
+}
+
+impl BvhLightSampler {
+    pub fn new(lights: &[Arc<dyn Light + Send + Sync>]) -> Self {
+        let mut sampler = Self {
+            nodes: Vec::new(),
+            infinite_lights: Vec::new(),
+            locations: vec![LightLocation::None; lights.len()],
+        };
+
+        let mut bounded = Vec::new();
+        for (index, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(bounds) if bounds.phi > 0.0 => bounded.push((index, bounds)),
+                Some(_) => {}
+                None => {
+                    sampler.infinite_lights.push(index);
+                    sampler.locations[index] = LightLocation::Infinite;
+                }
+            }
+        }
+
+        if !bounded.is_empty() {
+            sampler.build(&mut bounded, 0, 0);
+        }
+        sampler
+    }
+
+    /// Builds the subtree of `lights` and returns its root node.
+    ///
+    /// Lights are split in halves, so the depth never exceeds the bits of the trail.
+    fn build(&mut self, lights: &mut [(usize, LightBounds)], trail: u64, depth: u32) -> usize {
+        if lights.len() == 1 {
+            let (light, bounds) = lights[0];
+            self.locations[light] = LightLocation::Bvh { trail };
+            self.nodes.push(LightNode {
+                bounds,
+                kind: LightNodeKind::Leaf { light },
+            });
+            return self.nodes.len() - 1;
+        }
+
+        let mut centroids = Aabb::EMPTY;
+        for (_, bounds) in lights.iter() {
+            let center = bounds.bounds.center();
+            centroids = centroids.enclose(Aabb::from_corners(center, center));
+        }
+
+        let axis = centroids.longest_axis() as usize;
+        let key = |bounds: &LightBounds| {
+            let center = bounds.bounds.center();
+            [center.x, center.y, center.z][axis]
+        };
+        lights.sort_unstable_by(|(_, a), (_, b)| key(a).total_cmp(&key(b)));
+
+        let node = self.nodes.len();
+        self.nodes.push(LightNode {
+            bounds: lights[0].1,
+            kind: LightNodeKind::Interior { second_child: 0 },
+        });
+
+        let (left, right) = lights.split_at_mut(lights.len() / 2);
+        let first = self.build(left, trail, depth + 1);
+        let second = self.build(right, trail | (1 << depth), depth + 1);
+
+        self.nodes[node] = LightNode {
+            bounds: self.nodes[first].bounds.union(&self.nodes[second].bounds),
+            kind: LightNodeKind::Interior {
+                second_child: second,
+            },
+        };
+        node
+    }
+
+    /// Probability of choosing one of the lights at infinity.
+    fn infinite_probability(&self) -> f32 {
+        let n_infinite = self.infinite_lights.len() as f32;
+        let n_bvh = if self.nodes.is_empty() { 0.0 } else { 1.0 };
+        if n_infinite + n_bvh == 0.0 {
+            0.0
+        } else {
+            n_infinite / (n_infinite + n_bvh)
+        }
+    }
+
+    /// Returns the probabilities of choosing the children of an interior node.
+    fn child_probabilities(
+        &self,
+        node: usize,
+        second_child: usize,
+        point: Vec3,
+        normal: Vec3,
+    ) -> Option<[f32; 2]> {
+        let first = self.nodes[node + 1].bounds.importance(point, normal);
+        let second = self.nodes[second_child].bounds.importance(point, normal);
+        let sum = first + second;
+        if sum <= 0.0 {
+            return None;
+        }
+        Some([first / sum, second / sum])
+    }
+}
+
+impl LightSampler for BvhLightSampler {
+    fn sample(&self, point: Vec3, normal: Vec3, u: f32) -> Option<SampledLight> {
+        let p_infinite = self.infinite_probability();
+        if u < p_infinite {
+            let n = self.infinite_lights.len();
+            let index = ((u / p_infinite * n as f32) as usize).min(n - 1);
+            return Some(SampledLight {
+                index: self.infinite_lights[index],
+                pmf: p_infinite / n as f32,
+            });
+        }
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut u = ((u - p_infinite) / (1.0 - p_infinite)).min(1.0 - f32::EPSILON);
+        let mut pmf = 1.0 - p_infinite;
+        let mut node = 0;
+        loop {
+            match self.nodes[node].kind {
+                LightNodeKind::Leaf { light } => {
+                    if self.nodes[node].bounds.importance(point, normal) <= 0.0 {
+                        return None;
+                    }
+                    return Some(SampledLight { index: light, pmf });
+                }
+                LightNodeKind::Interior { second_child } => {
+                    let [p_first, p_second] =
+                        self.child_probabilities(node, second_child, point, normal)?;
+                    if u < p_first {
+                        node += 1;
+                        u /= p_first;
+                        pmf *= p_first;
+                    } else {
+                        node = second_child;
+                        u = (u - p_first) / p_second;
+                        pmf *= p_second;
+                    }
+                    u = u.min(1.0 - f32::EPSILON);
+                }
+            }
+        }
+    }
+
+    fn pmf(&self, point: Vec3, normal: Vec3, index: usize) -> f32 {
+        let mut trail = match self.locations.get(index) {
+            Some(LightLocation::Bvh { trail }) => *trail,
+            Some(LightLocation::Infinite) => {
+                return self.infinite_probability() / self.infinite_lights.len() as f32;
+            }
+            Some(LightLocation::None) | None => return 0.0,
+        };
+
+        let mut pmf = 1.0 - self.infinite_probability();
+        let mut node = 0;
+        while let LightNodeKind::Interior { second_child } = self.nodes[node].kind {
+            let Some(probabilities) = self.child_probabilities(node, second_child, point, normal)
+            else {
+                return 0.0;
+            };
+
+            let is_second = trail & 1 == 1;
+            pmf *= probabilities[is_second as usize];
+            node = if is_second { second_child } else { node + 1 };
+            trail >>= 1;
+        }
+        pmf
+    }
+}
+
+/// Union of the cones with axes `a` and `b` and cosines of their half angles.
+fn cone_union(a: Vec3, cos_a: f32, b: Vec3, cos_b: f32) -> (Vec3, f32) {
+    let theta_a = cos_a.clamp(-1.0, 1.0).acos();
+    let theta_b = cos_b.clamp(-1.0, 1.0).acos();
+    let theta_d = a.dot(&b).clamp(-1.0, 1.0).acos();
+
+    // One cone contains the other
+    if f32::min(theta_d + theta_b, PI) <= theta_a {
+        return (a, cos_a);
+    }
+    if f32::min(theta_d + theta_a, PI) <= theta_b {
+        return (b, cos_b);
+    }
+
+    let theta_o = (theta_a + theta_d + theta_b) / 2.0;
+    if theta_o >= PI {
+        return (a, -1.0);
+    }
+
+    // Rotate `a` towards `b` to the center of the merged cone
+    let theta_r = theta_o - theta_a;
+    let axis = a.cross(&b);
+    if axis.length_squared() == 0.0 {
+        return (a, -1.0);
+    }
+    let axis = axis.normalized();
+    let (sin_r, cos_r) = theta_r.sin_cos();
+    let w = a * cos_r + axis.cross(&a) * sin_r + axis * (axis.dot(&a) * (1.0 - cos_r));
+    (w.normalized(), theta_o.cos())
+}
+
+/// Cosine of `theta_a - theta_b`, `1.0` if the difference is negative.
+#[inline]
+fn cos_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> f32 {
+    if cos_a > cos_b {
+        1.0
+    } else {
+        cos_a * cos_b + sin_a * sin_b
+    }
+}
+
+/// Sine of `theta_a - theta_b`, `0.0` if the difference is negative.
+#[inline]
+fn sin_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> f32 {
+    if cos_a > cos_b {
+        0.0
+    } else {
+        sin_a * cos_b - cos_a * sin_b
+    }
+}
+
+#[inline]
+fn safe_sqrt(value: f32) -> f32 {
+    value.max(0.0).sqrt()
+}
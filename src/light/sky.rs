@@ -0,0 +1,345 @@
+use std::f32::consts::PI;
+
+use super::environment::{direction_to_uv, uv_to_direction};
+use crate::color::Color;
+use crate::light::{Light, LightSample};
+use crate::math::{Distribution2D, Onb, Vec2, Vec3};
+use crate::primitives::Ray;
+
+/// Resolution of the table used to importance sample the sky.
+const SAMPLING_WIDTH: usize = 64;
+const SAMPLING_HEIGHT: usize = 32;
+
+/// Angular radius of the sun disk seen from the earth in radians.
+const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+/// Luminance of the sun outside of the atmosphere in kcd/m^2.
+const SUN_LUMINANCE: f32 = 1.6e6;
+
+/// Parameters of the daylight model.
+#[derive(Clone, Copy, Debug)]
+pub struct SkyParams {
+    /// Angle of the sun above the horizon in radians.
+    pub sun_elevation: f32,
+    /// Angle of the sun around the `+Y` axis in radians, `0.0` is towards `-Z`, `PI / 2` towards `+X`.
+    pub sun_azimuth: f32,
+    /// Haziness of the atmosphere, `2.0` is a clear sky and `10.0` is a hazy one.
+    pub turbidity: f32,
+    /// Scale from luminance in kcd/m^2 to the scene radiance.
+    pub intensity: f32,
+    /// Reflectance of the ground, which reflects the horizon radiance below it.
+    pub ground_albedo: Color,
+}
+
+impl Default for SkyParams {
+    fn default() -> Self {
+        Self {
+            sun_elevation: f32::to_radians(45.0),
+            sun_azimuth: 0.0,
+            turbidity: 3.0,
+            intensity: 0.02,
+            ground_albedo: Color::new(0.3, 0.3, 0.3),
+        }
+    }
+}
+
+/// Coefficients of the Perez sky luminance distribution.
+#[derive(Clone, Copy, Debug)]
+struct Perez {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+}
+
+impl Perez {
+    fn eval(&self, cos_theta: f32, gamma: f32) -> f32 {
+        let cos_gamma = gamma.cos();
+        (1.0 + self.a * f32::exp(self.b / cos_theta.max(1e-3)))
+            * (1.0 + self.c * f32::exp(self.d * gamma) + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+/// Procedural daylight of Preetham, Shirley and Smits.
+///
+/// Only the sky dome is emitted, the sun disk is provided by [`SkyLight::sun`].
+#[derive(Clone, Debug)]
+pub struct SkyLight {
+    params: SkyParams,
+    sun_direction: Vec3,
+    sun_theta: f32,
+    /// Zenith values of luminance and chromaticity.
+    zenith: [f32; 3],
+    /// Distributions of luminance and chromaticity.
+    perez: [Perez; 3],
+    distribution: Distribution2D,
+}
+
+/// Sun disk at infinity, sampled uniformly over its solid angle.
+#[derive(Clone, Copy, Debug)]
+pub struct SunLight {
+    /// Direction towards the sun.
+    pub direction: Vec3,
+    /// Radiance of the sun disk.
+    pub radiance: Color,
+    /// Angular radius of the disk in radians.
+    pub angular_radius: f32,
+}
+
+impl SkyLight {
+    pub fn new(params: SkyParams) -> Self {
+        let t = params.turbidity.clamp(1.7, 10.0);
+        let (sin_el, cos_el) = params.sun_elevation.sin_cos();
+        let (sin_az, cos_az) = params.sun_azimuth.sin_cos();
+        let sun_direction = Vec3::new(cos_el * sin_az, sin_el, -cos_el * cos_az);
+        let sun_theta = (PI / 2.0 - params.sun_elevation).clamp(0.0, PI / 2.0);
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_theta);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let theta = [sun_theta.powi(3), sun_theta.powi(2), sun_theta, 1.0];
+        let turbidity = [t * t, t, 1.0];
+        let chromaticity = |m: [[f32; 4]; 3]| -> f32 {
+            (0..3)
+                .map(|i| turbidity[i] * (0..4).map(|j| m[i][j] * theta[j]).sum::<f32>())
+                .sum()
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let perez = [
+            Perez {
+                a: 0.1787 * t - 1.4630,
+                b: -0.3554 * t + 0.4275,
+                c: -0.0227 * t + 5.3251,
+                d: 0.1206 * t - 2.5771,
+                e: -0.0670 * t + 0.3703,
+            },
+            Perez {
+                a: -0.0193 * t - 0.2592,
+                b: -0.0665 * t + 0.0008,
+                c: -0.0004 * t + 0.2125,
+                d: -0.0641 * t - 0.8989,
+                e: -0.0033 * t + 0.0452,
+            },
+            Perez {
+                a: -0.0167 * t - 0.2608,
+                b: -0.0950 * t + 0.0092,
+                c: -0.0079 * t + 0.2102,
+                d: -0.0441 * t - 1.6537,
+                e: -0.0109 * t + 0.0529,
+            },
+        ];
+
+        let mut sky = Self {
+            params,
+            sun_direction,
+            sun_theta,
+            zenith: [zenith_luminance.max(0.0), zenith_x, zenith_y],
+            perez,
+            distribution: Distribution2D::new(&[1.0], 1, 1),
+        };
+
+        let mut func = Vec::with_capacity(SAMPLING_WIDTH * SAMPLING_HEIGHT);
+        for y in 0..SAMPLING_HEIGHT {
+            for x in 0..SAMPLING_WIDTH {
+                let u = (x as f32 + 0.5) / SAMPLING_WIDTH as f32;
+                let v = (y as f32 + 0.5) / SAMPLING_HEIGHT as f32;
+                let (dir, sin_theta) = uv_to_direction(u, v);
+                func.push(sky.radiance(dir).luminance().max(0.0) * sin_theta);
+            }
+        }
+        sky.distribution = Distribution2D::new(&func, SAMPLING_WIDTH, SAMPLING_HEIGHT);
+        sky
+    }
+
+    pub fn params(&self) -> &SkyParams {
+        &self.params
+    }
+
+    /// Direction towards the sun.
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    /// Returns the sun disk matching the sky, attenuated by the atmosphere.
+    pub fn sun(&self) -> SunLight {
+        SunLight {
+            direction: self.sun_direction,
+            radiance: self.sun_transmittance() * (SUN_LUMINANCE * self.params.intensity),
+            angular_radius: SUN_ANGULAR_RADIUS,
+        }
+    }
+
+    /// Radiance of the sky arriving from the direction `dir`.
+    pub fn radiance(&self, dir: Vec3) -> Color {
+        let mut dir = dir.normalized();
+        let mut albedo = Color::WHITE;
+        if dir.y <= 0.0 {
+            // Ground reflects the sky radiance of the horizon
+            let horizontal = Vec3::new(dir.x, 0.0, dir.z);
+            dir = if horizontal.length_squared() > 0.0 {
+                horizontal.normalized()
+            } else {
+                Vec3::X
+            };
+            albedo = self.params.ground_albedo;
+        }
+
+        let gamma = dir.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [y, x, chroma_y] = std::array::from_fn(|i| {
+            let perez = &self.perez[i];
+            let normalization = perez.eval(1.0, self.sun_theta);
+            self.zenith[i] * perez.eval(dir.y, gamma) / normalization
+        });
+
+        xyy_to_rgb(x, chroma_y, y * self.params.intensity) * albedo
+    }
+
+    /// Spectral attenuation of the sun by Rayleigh and aerosol scattering.
+    fn sun_transmittance(&self) -> Color {
+        if self.sun_direction.y <= 0.0 {
+            return Color::BLACK;
+        }
+
+        let t = self.params.turbidity.clamp(1.7, 10.0);
+        let theta_deg = self.sun_theta.to_degrees();
+        let air_mass = 1.0 / (self.sun_theta.cos() + 0.15 * (93.885 - theta_deg).powf(-1.253));
+        let beta = 0.04608 * t - 0.04586;
+
+        // Representative wavelengths of the red, green and blue channels in micrometers
+        let [r, g, b] = [0.68f32, 0.55, 0.44].map(|lambda| {
+            let rayleigh = 0.008735 * lambda.powf(-4.08);
+            let aerosol = beta * lambda.powf(-1.3);
+            f32::exp(-air_mass * (rayleigh + aerosol))
+        });
+        Color::new(r, g, b)
+    }
+}
+
+impl Light for SkyLight {
+    fn sample_li(&self, _point: Vec3, u: Vec2) -> Option<LightSample> {
+        let ((u, v), uv_pdf) = self.distribution.sample_continuous(u.x, u.y);
+        let (wi, sin_theta) = uv_to_direction(u, v);
+        if uv_pdf <= 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            wi,
+            radiance: self.radiance(wi),
+            distance: f32::INFINITY,
+            pdf: uv_pdf / (2.0 * PI * PI * sin_theta),
+        })
+    }
+
+    fn pdf_li(&self, _point: Vec3, wi: Vec3) -> f32 {
+        let (u, v) = direction_to_uv(wi.normalized());
+        let sin_theta = f32::sin(v * PI);
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn le(&self, ray: &Ray) -> Color {
+        self.radiance(ray.direction())
+    }
+
+    fn power(&self, scene_radius: f32) -> Color {
+        let mut sum = Color::BLACK;
+        for y in 0..SAMPLING_HEIGHT {
+            for x in 0..SAMPLING_WIDTH {
+                let u = (x as f32 + 0.5) / SAMPLING_WIDTH as f32;
+                let v = (y as f32 + 0.5) / SAMPLING_HEIGHT as f32;
+                let (dir, sin_theta) = uv_to_direction(u, v);
+                sum += self.radiance(dir) * sin_theta;
+            }
+        }
+
+        let solid_angle = 2.0 * PI * PI / (SAMPLING_WIDTH * SAMPLING_HEIGHT) as f32;
+        sum * (solid_angle * PI * scene_radius * scene_radius)
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
+
+impl SunLight {
+    fn cos_max(&self) -> f32 {
+        self.angular_radius.cos()
+    }
+}
+
+impl Light for SunLight {
+    fn sample_li(&self, _point: Vec3, u: Vec2) -> Option<LightSample> {
+        let cos_max = self.cos_max();
+        let cos_theta = 1.0 - u.x * (1.0 - cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.y;
+
+        let onb = Onb::from_normal(self.direction.normalized());
+        let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        Some(LightSample {
+            wi: onb.to_world(local),
+            radiance: self.radiance,
+            distance: f32::INFINITY,
+            pdf: 1.0 / (2.0 * PI * (1.0 - cos_max)),
+        })
+    }
+
+    fn pdf_li(&self, _point: Vec3, wi: Vec3) -> f32 {
+        let cos_max = self.cos_max();
+        if wi.normalized().dot(&self.direction.normalized()) >= cos_max {
+            1.0 / (2.0 * PI * (1.0 - cos_max))
+        } else {
+            0.0
+        }
+    }
+
+    fn power(&self, scene_radius: f32) -> Color {
+        let solid_angle = 2.0 * PI * (1.0 - self.cos_max());
+        self.radiance * (solid_angle * PI * scene_radius * scene_radius)
+    }
+
+    fn le(&self, ray: &Ray) -> Color {
+        let cos_theta = ray
+            .direction()
+            .normalized()
+            .dot(&self.direction.normalized());
+        if cos_theta >= self.cos_max() {
+            self.radiance
+        } else {
+            Color::BLACK
+        }
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
+
+/// Converts CIE xyY to linear sRGB.
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Color {
+    if y <= 0.0 {
+        return Color::BLACK;
+    }
+
+    let cx = x * luminance / y;
+    let cz = (1.0 - x - y) * luminance / y;
+    let cy = luminance;
+    Color::new(
+        (3.2406 * cx - 1.5372 * cy - 0.4986 * cz).max(0.0),
+        (-0.9689 * cx + 1.8758 * cy + 0.0415 * cz).max(0.0),
+        (0.0557 * cx - 0.2040 * cy + 1.0570 * cz).max(0.0),
+    )
+}
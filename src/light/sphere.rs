@@ -0,0 +1,131 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::light::{Light, LightBounds, LightSample};
+use crate::material::EmissiveMaterial;
+use crate::math::{Aabb, Interval, Onb, Vec2, Vec3};
+use crate::primitives::{HitRecord, Hittable, Ray, Sphere};
+
+/// Emissive sphere which is both part of the geometry and a light.
+///
+/// Add the same `Arc` to the world and to the scene lights,
+/// so hits of the sphere are recognized as hits of the light.
+#[derive(Clone, Debug)]
+pub struct SphereLight {
+    sphere: Sphere,
+    radiance: Color,
+}
+
+impl SphereLight {
+    pub fn new(center: Vec3, radius: f32, radiance: Color) -> Self {
+        Self {
+            sphere: Sphere {
+                center,
+                radius,
+                material: Arc::new(EmissiveMaterial { radiance }),
+            },
+            radiance,
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        self.sphere.center
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.sphere.radius
+    }
+
+    /// Returns `1 - cos(theta_max)` of the cone subtended by the sphere from `point`,
+    /// `None` if the point is inside.
+    fn cone(&self, point: Vec3) -> Option<f32> {
+        let distance_squared = (self.sphere.center - point).length_squared();
+        let radius_squared = self.sphere.radius * self.sphere.radius;
+        if distance_squared <= radius_squared {
+            return None;
+        }
+
+        let sin2_max = radius_squared / distance_squared;
+        let cos_max = (1.0 - sin2_max).max(0.0).sqrt();
+        // Avoids cancellation for small and distant spheres
+        Some(sin2_max / (1.0 + cos_max))
+    }
+}
+
+impl Hittable for SphereLight {
+    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let hit = self.sphere.hit(ray, t_range)?;
+        Some(HitRecord {
+            light: Some(self),
+            ..hit
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.sphere.bounding_box()
+    }
+}
+
+impl Light for SphereLight {
+    fn sample_li(&self, point: Vec3, u: Vec2) -> Option<LightSample> {
+        let one_minus_cos_max = self.cone(point)?;
+        let to_center = self.sphere.center - point;
+        let distance_center = to_center.length();
+
+        let one_minus_cos = u.x * one_minus_cos_max;
+        let cos_theta = 1.0 - one_minus_cos;
+        let sin2_theta = one_minus_cos * (2.0 - one_minus_cos);
+        let sin_theta = sin2_theta.max(0.0).sqrt();
+        let phi = 2.0 * PI * u.y;
+
+        let onb = Onb::from_normal(to_center / distance_center);
+        let wi = onb.to_world(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+
+        // Nearest intersection of the sampled direction with the sphere
+        let radius_squared = self.sphere.radius * self.sphere.radius;
+        let half_chord = (radius_squared - distance_center * distance_center * sin2_theta)
+            .max(0.0)
+            .sqrt();
+        let distance = distance_center * cos_theta - half_chord;
+
+        Some(LightSample {
+            wi,
+            radiance: self.radiance,
+            distance,
+            pdf: 1.0 / (2.0 * PI * one_minus_cos_max),
+        })
+    }
+
+    fn pdf_li(&self, point: Vec3, wi: Vec3) -> f32 {
+        let Some(one_minus_cos_max) = self.cone(point) else {
+            return 0.0;
+        };
+
+        let to_center = (self.sphere.center - point).normalized();
+        if 1.0 - wi.normalized().dot(&to_center) > one_minus_cos_max {
+            return 0.0;
+        }
+        1.0 / (2.0 * PI * one_minus_cos_max)
+    }
+
+    fn power(&self, _scene_radius: f32) -> Color {
+        let area = 4.0 * PI * self.sphere.radius * self.sphere.radius;
+        self.radiance * (PI * area)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds {
+            bounds: self.sphere.bounding_box(),
+            direction: Vec3::Z,
+            phi: self.power(0.0).luminance(),
+            cos_theta_o: -1.0,
+            cos_theta_e: 0.0,
+            two_sided: false,
+        })
+    }
+}
@@ -1,12 +1,116 @@
+mod alpha_mask;
+mod coated;
+mod emissive;
+pub(crate) mod microfacet;
+mod normal_map;
+mod oren_nayar;
+mod principled;
+mod sheen;
+
+pub use alpha_mask::*;
+pub use coated::*;
+pub use emissive::*;
+pub use normal_map::*;
+pub use oren_nayar::*;
+pub use principled::*;
+pub use sheen::*;
+
+use std::f32::consts::FRAC_1_PI;
+use std::fmt;
+
 use crate::color::Color;
-use crate::math::Vec3;
+use crate::material::microfacet::sample_cosine_hemisphere;
+use crate::math::{Onb, Vec2, Vec3};
 use crate::primitives::{HitRecord, Ray};
+use crate::sampler::{Sampler, sample_uniform_sphere};
+
+/// Surface scattering model.
+///
+/// Directions point away from the surface, `wo` towards the viewer and `wi` towards the light.
+/// Implement it to plug custom materials into the renderer.
+pub trait Bsdf: fmt::Debug {
+    /// Samples an incident direction for the outgoing direction `wo`.
+    ///
+    /// The uniform sample `uc` chooses between lobes, `u` samples the direction within the lobe.
+    fn sample(&self, wo: Vec3, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample>;
+
+    /// Evaluates the BSDF for a pair of directions.
+    ///
+    /// Specular lobes can not be evaluated and contribute nothing.
+    fn eval(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> Color;
+
+    /// Returns the solid angle density of sampling `wi` given `wo`.
+    fn pdf(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> f32;
+
+    /// Returns radiance emitted from the surface.
+    fn emitted(&self, _hit: &HitRecord) -> Color {
+        Color::BLACK
+    }
+
+    /// Returns the opacity of the surface in `[0.0, 1.0]`.
+    ///
+    /// Intersections are discarded with the probability of `1.0 - opacity`,
+    /// so masked regions are skipped both by camera and shadow rays.
+    fn opacity(&self, _hit: &HitRecord) -> f32 {
+        1.0
+    }
+
+    /// Samples the BSDF and returns the attenuation and the scattered ray.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Color, Ray)> {
+        let sample = self.sample(
+            -ray.direction().normalized(),
+            hit,
+            sampler.get_1d(),
+            sampler.get_2d(),
+        )?;
+        if sample.pdf <= 0.0 {
+            return None;
+        }
+
+        let cos_theta = sample.wi.dot(&hit.normal).abs();
+        let attenuation = sample.f * (cos_theta / sample.pdf);
+        Some((attenuation, hit.spawn_ray(sample.wi)))
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 pub enum Material {
     Lambertian(LambertianMaterial),
+    OrenNayar(OrenNayarMaterial),
     Metalic(MetalicMaterial),
     Dielectric(DielectricMaterial),
+    Principled(PrincipledMaterial),
+}
+
+/// Direction sampled from a BSDF.
+#[derive(Clone, Copy, Debug)]
+pub struct BsdfSample {
+    /// Sampled incident direction in world space.
+    pub wi: Vec3,
+    /// BSDF value for the sampled direction.
+    pub f: Color,
+    /// Solid angle density of the sampled direction, `1.0` for specular samples.
+    pub pdf: f32,
+    /// Whether the direction was sampled from a specular lobe.
+    pub is_specular: bool,
+}
+
+impl BsdfSample {
+    /// Creates a sample of a specular lobe that reflects `attenuation` of incoming radiance.
+    pub fn specular(wi: Vec3, attenuation: Color, hit: &HitRecord) -> Self {
+        let cos_theta = wi.normalized().dot(&hit.normal).abs();
+        Self {
+            wi,
+            f: attenuation * cos_theta.recip(),
+            pdf: 1.0,
+            is_specular: true,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -32,6 +136,10 @@ impl Material {
         Self::Lambertian(LambertianMaterial { albedo })
     }
 
+    pub const fn oren_nayar(albedo: Color, roughness: f32) -> Self {
+        Self::OrenNayar(OrenNayarMaterial { albedo, roughness })
+    }
+
     pub const fn metalic(albedo: Color, fuzz: f32) -> Self {
         Self::Metalic(MetalicMaterial { albedo, fuzz })
     }
@@ -40,23 +148,32 @@ impl Material {
         Self::Dielectric(DielectricMaterial { ior })
     }
 
-    pub fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Color, Ray)> {
+    pub const fn principled(principled: PrincipledMaterial) -> Self {
+        Self::Principled(principled)
+    }
+}
+
+impl Bsdf for Material {
+    fn sample(&self, wo: Vec3, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample> {
         match self {
             Self::Lambertian(mat) => {
-                let mut scatter_dir = hit.normal + Vec3::random_on_sphere();
-                if scatter_dir.relative_eq(&Vec3::ZERO) {
-                    scatter_dir = hit.normal;
-                }
-                let scattered_ray = Ray::new(hit.point, scatter_dir);
-                Some((mat.albedo, scattered_ray))
+                let wi = Onb::from_normal(hit.normal).to_world(sample_cosine_hemisphere(u));
+                Some(BsdfSample {
+                    wi,
+                    f: mat.albedo * FRAC_1_PI,
+                    pdf: wi.dot(&hit.normal) * FRAC_1_PI,
+                    is_specular: false,
+                })
             }
+            Self::OrenNayar(mat) => mat.sample(wo, hit, u),
             Self::Metalic(mat) => {
-                let reflect_dir = ray.direction().reflect(&hit.normal);
-                let fuzzed_dir = reflect_dir.normalized() + (Vec3::random_on_sphere() * mat.fuzz);
-                let scattered_ray = Ray::new(hit.point, fuzzed_dir);
+                let reflect_dir = (-wo).reflect(&hit.normal);
+                let fuzzed_dir = reflect_dir.normalized() + (sample_uniform_sphere(u) * mat.fuzz);
+                let cos_theta = fuzzed_dir.dot(&hit.normal);
 
-                if scattered_ray.direction().dot(&hit.normal) > 0.0 {
-                    Some((mat.albedo, scattered_ray))
+                if cos_theta > 0.0 {
+                    let wi = fuzzed_dir.normalized();
+                    Some(BsdfSample::specular(wi, mat.albedo, hit))
                 } else {
                     None
                 }
@@ -68,18 +185,56 @@ impl Material {
                     mat.ior
                 };
 
-                let ray_dir = ray.direction().normalized();
+                let ray_dir = -wo.normalized();
                 let mut refracted_dir = ray_dir.refract(&hit.normal, ior);
                 let cos_theta = f32::min(-ray_dir.dot(&hit.normal), 1.0);
 
                 // Can not refract - total internal reflection
-                if refracted_dir == Vec3::ZERO || reflectance(cos_theta, ior) > fastrand::f32() {
+                if refracted_dir == Vec3::ZERO || reflectance(cos_theta, ior) > uc {
                     refracted_dir = ray_dir.reflect(&hit.normal);
                 }
 
-                let scattered_ray = Ray::new(hit.point, refracted_dir);
-                Some((Color::WHITE, scattered_ray))
+                Some(BsdfSample::specular(refracted_dir, Color::WHITE, hit))
+            }
+            Self::Principled(mat) => mat.sample(wo, hit, uc, u),
+        }
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> Color {
+        match self {
+            Self::Lambertian(mat) => {
+                if wi.dot(&hit.normal) > 0.0 {
+                    mat.albedo * FRAC_1_PI
+                } else {
+                    Color::BLACK
+                }
             }
+            Self::OrenNayar(mat) => mat.eval(wo, wi, hit),
+            Self::Metalic(_) | Self::Dielectric(_) => Color::BLACK,
+            Self::Principled(mat) => mat.eval(wo, wi, hit),
+        }
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> f32 {
+        match self {
+            Self::Lambertian(_) => f32::max(wi.normalized().dot(&hit.normal), 0.0) * FRAC_1_PI,
+            Self::OrenNayar(mat) => mat.pdf(wo, wi, hit),
+            Self::Metalic(_) | Self::Dielectric(_) => 0.0,
+            Self::Principled(mat) => mat.pdf(wo, wi, hit),
+        }
+    }
+
+    fn emitted(&self, _hit: &HitRecord) -> Color {
+        match self {
+            Self::Principled(mat) => mat.emission,
+            _ => Color::BLACK,
+        }
+    }
+
+    fn opacity(&self, _hit: &HitRecord) -> f32 {
+        match self {
+            Self::Principled(mat) => mat.opacity,
+            _ => 1.0,
         }
     }
 }
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::material::{Bsdf, BsdfSample};
+use crate::math::{Vec2, Vec3};
+use crate::primitives::HitRecord;
+use crate::texture::Texture;
+
+/// Cuts out parts of a base material with an opacity texture.
+///
+/// Used for leaves, fences and decals modeled as simple quads.
+#[derive(Clone, Debug)]
+pub struct AlphaMaskedMaterial {
+    pub base: Arc<dyn Bsdf + Send + Sync>,
+    /// Opacity texture, its luminance is used as the opacity.
+    pub mask: Arc<Texture>,
+    /// Turns the mask into a binary cutout, opacity below the cutoff is fully transparent.
+    /// Without cutoff, the opacity is used as a probability of the surface being hit.
+    pub cutoff: Option<f32>,
+}
+
+impl AlphaMaskedMaterial {
+    pub fn new(base: Arc<dyn Bsdf + Send + Sync>, mask: Arc<Texture>) -> Self {
+        Self {
+            base,
+            mask,
+            cutoff: Some(0.5),
+        }
+    }
+}
+
+impl Bsdf for AlphaMaskedMaterial {
+    fn sample(&self, wo: Vec3, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample> {
+        self.base.sample(wo, hit, uc, u)
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> Color {
+        self.base.eval(wo, wi, hit)
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> f32 {
+        self.base.pdf(wo, wi, hit)
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        self.base.emitted(hit)
+    }
+
+    fn opacity(&self, hit: &HitRecord) -> f32 {
+        let opacity = self.mask.sample(hit.uv).luminance().clamp(0.0, 1.0) * self.base.opacity(hit);
+        match self.cutoff {
+            Some(cutoff) if opacity < cutoff => 0.0,
+            Some(_) => 1.0,
+            None => opacity,
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::material::microfacet::{Ggx, fresnel_dielectric, reflect, refract};
+use crate::material::{Bsdf, BsdfSample};
+use crate::math::{Onb, Vec2, Vec3};
+use crate::primitives::HitRecord;
+use crate::sampler::ONE_MINUS_EPSILON;
+
+/// Dielectric coating layered over an arbitrary base material.
+///
+/// Light is either reflected by the coating or refracted into it,
+/// attenuated by the absorbing coating layer and scattered by the base.
+/// Interreflections between the coating and the base are ignored.
+#[derive(Clone, Debug)]
+pub struct CoatedMaterial {
+    pub base: Arc<dyn Bsdf + Send + Sync>,
+    /// Index of refraction of the coating.
+    pub ior: f32,
+    /// Roughness of the coating surface, `0.0` is perfectly smooth.
+    pub roughness: f32,
+    /// Thickness of the coating in world units.
+    pub thickness: f32,
+    /// Absorption coefficient of the coating per world unit.
+    pub absorption: Color,
+}
+
+impl CoatedMaterial {
+    /// Creates a clear smooth coating over `base`.
+    pub fn new(base: Arc<dyn Bsdf + Send + Sync>) -> Self {
+        Self {
+            base,
+            ior: 1.5,
+            roughness: 0.0,
+            thickness: 0.0,
+            absorption: Color::BLACK,
+        }
+    }
+
+    fn is_smooth(&self) -> bool {
+        self.roughness <= 0.0
+    }
+
+    fn distribution(&self) -> Ggx {
+        Ggx::from_roughness(self.roughness)
+    }
+
+    /// Probability of sampling the coating instead of the base.
+    fn coat_probability(&self, wo: Vec3) -> f32 {
+        fresnel_dielectric(wo.z, self.ior)
+    }
+
+    /// Maps an outside direction to the direction inside the coating,
+    /// both pointing away from the base.
+    fn to_inside(&self, w: Vec3) -> Option<Vec3> {
+        refract(w, Vec3::Z, self.ior).map(|t| -t)
+    }
+
+    /// Attenuation of light travelling down and up through the coating.
+    fn transmittance(&self, cos_o: f32, cos_i: f32) -> Color {
+        if self.thickness <= 0.0 {
+            return Color::WHITE;
+        }
+
+        let distance = self.thickness * (cos_o.recip() + cos_i.recip());
+        Color::new(
+            f32::exp(-self.absorption.r() * distance),
+            f32::exp(-self.absorption.g() * distance),
+            f32::exp(-self.absorption.b() * distance),
+        )
+    }
+
+    fn eval_coat(&self, wo: Vec3, wi: Vec3) -> Color {
+        if self.is_smooth() {
+            return Color::BLACK;
+        }
+
+        let wm = (wo + wi).normalized();
+        let distribution = self.distribution();
+        let fresnel = fresnel_dielectric(wo.dot(&wm), self.ior);
+        let f = distribution.d(wm) * distribution.g(wo, wi) * fresnel / (4.0 * wo.z * wi.z);
+        Color::WHITE * f
+    }
+
+    fn pdf_coat(&self, wo: Vec3, wi: Vec3) -> f32 {
+        if self.is_smooth() {
+            return 0.0;
+        }
+
+        let wm = (wo + wi).normalized();
+        self.distribution().pdf(wo, wm) / (4.0 * wo.dot(&wm).abs())
+    }
+
+    fn eval_base(&self, wo: Vec3, wi: Vec3, onb: &Onb, hit: &HitRecord) -> Color {
+        let (Some(wo_base), Some(wi_base)) = (self.to_inside(wo), self.to_inside(wi)) else {
+            return Color::BLACK;
+        };
+
+        let f = self
+            .base
+            .eval(onb.to_world(wo_base), onb.to_world(wi_base), hit);
+        let fresnel_o = fresnel_dielectric(wo.z, self.ior);
+        let fresnel_i = fresnel_dielectric(wi.z, self.ior);
+        let transmittance = self.transmittance(wo_base.z, wi_base.z);
+
+        // Solid angle is compressed by the refraction into the coating
+        f * transmittance * ((1.0 - fresnel_o) * (1.0 - fresnel_i) / (self.ior * self.ior))
+    }
+
+    fn pdf_base(&self, wo: Vec3, wi: Vec3, onb: &Onb, hit: &HitRecord) -> f32 {
+        let (Some(wo_base), Some(wi_base)) = (self.to_inside(wo), self.to_inside(wi)) else {
+            return 0.0;
+        };
+
+        let pdf = self
+            .base
+            .pdf(onb.to_world(wo_base), onb.to_world(wi_base), hit);
+        pdf * wi.z / (self.ior * self.ior * wi_base.z)
+    }
+}
+
+impl Bsdf for CoatedMaterial {
+    fn sample(&self, wo: Vec3, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample> {
+        let onb = Onb::from_normal(hit.normal);
+        let wo = onb.to_local(wo.normalized());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let coat_probability = self.coat_probability(wo);
+        if uc < coat_probability {
+            if self.is_smooth() {
+                let wi = reflect(wo, Vec3::Z);
+                return Some(BsdfSample::specular(onb.to_world(wi), Color::WHITE, hit));
+            }
+
+            let wm = self.distribution().sample_wm(wo, u);
+            let wi = reflect(wo, wm);
+            if wi.z <= 0.0 {
+                return None;
+            }
+
+            return Some(BsdfSample {
+                wi: onb.to_world(wi),
+                f: self.eval(onb.to_world(wo), onb.to_world(wi), hit),
+                pdf: self.pdf(onb.to_world(wo), onb.to_world(wi), hit),
+                is_specular: false,
+            });
+        }
+
+        let wo_base = self.to_inside(wo)?;
+        // Reuses the lobe sample remapped to `[0.0, 1.0)` for the base
+        let uc = ((uc - coat_probability) / (1.0 - coat_probability)).min(ONE_MINUS_EPSILON);
+        let base_sample = self.base.sample(onb.to_world(wo_base), hit, uc, u)?;
+        let wi_base = onb.to_local(base_sample.wi.normalized());
+        if wi_base.z <= 0.0 {
+            return None;
+        }
+
+        // Light leaving the base through the coating, total internal reflection is absorbed
+        let wi = refract(-wi_base, Vec3::Z, self.ior)?;
+
+        if base_sample.is_specular {
+            let fresnel_o = fresnel_dielectric(wo.z, self.ior);
+            let fresnel_i = fresnel_dielectric(wi.z, self.ior);
+            let transmittance = self.transmittance(wo_base.z, wi_base.z);
+            let cos_base = wi_base.z;
+            let attenuation = base_sample.f
+                * transmittance
+                * ((1.0 - fresnel_o) * (1.0 - fresnel_i) * cos_base
+                    / (base_sample.pdf * (1.0 - coat_probability)));
+            return Some(BsdfSample::specular(onb.to_world(wi), attenuation, hit));
+        }
+
+        let wo_world = onb.to_world(wo);
+        let wi_world = onb.to_world(wi);
+        Some(BsdfSample {
+            wi: wi_world,
+            f: self.eval(wo_world, wi_world, hit),
+            pdf: self.pdf(wo_world, wi_world, hit),
+            is_specular: false,
+        })
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> Color {
+        let onb = Onb::from_normal(hit.normal);
+        let wo = onb.to_local(wo.normalized());
+        let wi = onb.to_local(wi.normalized());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::BLACK;
+        }
+
+        self.eval_coat(wo, wi) + self.eval_base(wo, wi, &onb, hit)
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> f32 {
+        let onb = Onb::from_normal(hit.normal);
+        let wo = onb.to_local(wo.normalized());
+        let wi = onb.to_local(wi.normalized());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+
+        let coat_probability = self.coat_probability(wo);
+        coat_probability * self.pdf_coat(wo, wi)
+            + (1.0 - coat_probability) * self.pdf_base(wo, wi, &onb, hit)
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        self.base.emitted(hit)
+    }
+
+    fn opacity(&self, hit: &HitRecord) -> f32 {
+        self.base.opacity(hit)
+    }
+}
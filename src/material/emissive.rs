@@ -0,0 +1,33 @@
+use crate::color::Color;
+use crate::material::{Bsdf, BsdfSample};
+use crate::math::{Vec2, Vec3};
+use crate::primitives::HitRecord;
+
+/// Surface emitting light from its front side without scattering any.
+#[derive(Clone, Copy, Debug)]
+pub struct EmissiveMaterial {
+    /// Emitted radiance.
+    pub radiance: Color,
+}
+
+impl Bsdf for EmissiveMaterial {
+    fn sample(&self, _wo: Vec3, _hit: &HitRecord, _uc: f32, _u: Vec2) -> Option<BsdfSample> {
+        None
+    }
+
+    fn eval(&self, _wo: Vec3, _wi: Vec3, _hit: &HitRecord) -> Color {
+        Color::BLACK
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3, _hit: &HitRecord) -> f32 {
+        0.0
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        if hit.is_front_face {
+            self.radiance
+        } else {
+            Color::BLACK
+        }
+    }
+}
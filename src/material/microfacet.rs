@@ -0,0 +1,202 @@
+use std::f32::consts::PI;
+
+use crate::math::{Vec2, Vec3};
+
+/// Isotropic Trowbridge-Reitz (GGX) microfacet distribution.
+///
+/// All directions are in the local shading space, where the normal is `+Z`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Ggx {
+    alpha: f32,
+}
+
+impl Ggx {
+    /// Lowest alpha that is still numerically stable in `f32`.
+    const MIN_ALPHA: f32 = 2e-3;
+
+    pub(crate) fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.max(Self::MIN_ALPHA),
+        }
+    }
+
+    /// Maps perceptual roughness to alpha.
+    pub(crate) fn from_roughness(roughness: f32) -> Self {
+        Self::new(roughness * roughness)
+    }
+
+    /// Microfacet normal distribution `D(wm)`.
+    #[inline]
+    pub(crate) fn d(&self, wm: Vec3) -> f32 {
+        let cos2 = wm.z * wm.z;
+        if cos2 <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha * self.alpha;
+        let t = cos2 * (a2 - 1.0) + 1.0;
+        a2 / (PI * t * t)
+    }
+
+    #[inline]
+    fn lambda(&self, w: Vec3) -> f32 {
+        let cos2 = w.z * w.z;
+        if cos2 <= 0.0 {
+            return 0.0;
+        }
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        0.5 * (f32::sqrt(1.0 + self.alpha * self.alpha * tan2) - 1.0)
+    }
+
+    /// Smith masking function.
+    #[inline]
+    pub(crate) fn g1(&self, w: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated Smith masking-shadowing function.
+    #[inline]
+    pub(crate) fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of visible normals `wm` as seen from `w`.
+    #[inline]
+    pub(crate) fn pdf(&self, w: Vec3, wm: Vec3) -> f32 {
+        let cos = w.z.abs();
+        if cos == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / cos * self.d(wm) * w.dot(&wm).abs()
+    }
+
+    /// Samples a visible microfacet normal as seen from `w` (Heitz 2018).
+    pub(crate) fn sample_wm(&self, w: Vec3, u: Vec2) -> Vec3 {
+        let mut wh = Vec3::new(self.alpha * w.x, self.alpha * w.y, w.z).normalized();
+        if wh.z < 0.0 {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z < 0.99999 {
+            Vec3::Z.cross(&wh).normalized()
+        } else {
+            Vec3::X
+        };
+        let t2 = wh.cross(&t1);
+
+        let r = u.x.sqrt();
+        let phi = 2.0 * PI * u.y;
+        let px = r * phi.cos();
+        let mut py = r * phi.sin();
+        let h = f32::sqrt(1.0 - px * px);
+        let s = 0.5 * (1.0 + wh.z);
+        py = (1.0 - s) * h + s * py;
+        let pz = f32::sqrt((1.0 - px * px - py * py).max(0.0));
+
+        let nh = t1 * px + t2 * py + wh * pz;
+        Vec3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(1e-6)).normalized()
+    }
+}
+
+/// Generalized Trowbridge-Reitz distribution with `gamma = 1`,
+/// used by the clearcoat lobe.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Gtr1 {
+    alpha: f32,
+}
+
+impl Gtr1 {
+    pub(crate) fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(1e-3, 0.999),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn d(&self, wm: Vec3) -> f32 {
+        let a2 = self.alpha * self.alpha;
+        let t = 1.0 + (a2 - 1.0) * wm.z * wm.z;
+        (a2 - 1.0) / (PI * a2.ln() * t)
+    }
+
+    /// Density of the half vector `wm`, proportional to `D(wm) cos(wm)`.
+    #[inline]
+    pub(crate) fn pdf(&self, wm: Vec3) -> f32 {
+        self.d(wm) * wm.z.abs()
+    }
+
+    pub(crate) fn sample_wm(&self, u: Vec2) -> Vec3 {
+        let a2 = self.alpha * self.alpha;
+        let cos2 = (1.0 - a2.powf(1.0 - u.x)) / (1.0 - a2);
+        let cos = cos2.sqrt();
+        let sin = (1.0 - cos2).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.y;
+        Vec3::new(sin * phi.cos(), sin * phi.sin(), cos)
+    }
+}
+
+/// Schlick's approximation of the Fresnel reflectance.
+#[inline]
+pub(crate) fn schlick_weight(cos: f32) -> f32 {
+    let m = (1.0 - cos).clamp(0.0, 1.0);
+    let m2 = m * m;
+    m2 * m2 * m
+}
+
+/// Exact unpolarized Fresnel reflectance of a dielectric interface.
+///
+/// `eta` is the relative index of refraction of the transmitted side.
+pub(crate) fn fresnel_dielectric(cos_i: f32, eta: f32) -> f32 {
+    let (cos_i, eta) = if cos_i < 0.0 {
+        (-cos_i, eta.recip())
+    } else {
+        (cos_i, eta)
+    };
+    let cos_i = cos_i.min(1.0);
+
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).max(0.0).sqrt();
+
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parl * r_parl + r_perp * r_perp) / 2.0
+}
+
+/// Refracts `wi` about the normal `n` with relative index of refraction `eta`.
+///
+/// Unlike [`Vec3::refract`] the incident direction points away from the surface.
+pub(crate) fn refract(wi: Vec3, n: Vec3, eta: f32) -> Option<Vec3> {
+    let (n, eta, cos_i) = {
+        let cos_i = n.dot(&wi);
+        if cos_i < 0.0 {
+            (-n, eta.recip(), -cos_i)
+        } else {
+            (n, eta, cos_i)
+        }
+    };
+
+    let sin2_i = (1.0 - cos_i * cos_i).max(0.0);
+    let sin2_t = sin2_i / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-wi / eta + n * (cos_i / eta - cos_t))
+}
+
+/// Reflects `wo` about the normal `n`, both pointing away from the surface.
+#[inline]
+pub(crate) fn reflect(wo: Vec3, n: Vec3) -> Vec3 {
+    -wo + n * 2.0 * wo.dot(&n)
+}
+
+/// Cosine weighted direction on the `+Z` hemisphere.
+#[inline]
+pub(crate) fn sample_cosine_hemisphere(u: Vec2) -> Vec3 {
+    let r = u.x.sqrt();
+    let phi = 2.0 * PI * u.y;
+    let z = (1.0 - u.x).max(0.0).sqrt();
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::material::{Bsdf, BsdfSample};
+use crate::math::{Vec2, Vec3};
+use crate::primitives::HitRecord;
+use crate::texture::Texture;
+
+/// Perturbs the shading normal of a base material with a normal map or a bump map.
+///
+/// The geometric normal is left untouched, so the side of the surface
+/// and the ray offsets are still decided by the real geometry.
+#[derive(Clone, Debug)]
+pub struct NormalMappedMaterial {
+    pub base: Arc<dyn Bsdf + Send + Sync>,
+    /// Tangent space normal map, stored with linear encoding.
+    pub normal_map: Option<Arc<Texture>>,
+    /// Height map, its luminance is used as the height.
+    pub bump_map: Option<Arc<Texture>>,
+    /// Scale of the height map gradient.
+    pub bump_strength: f32,
+}
+
+impl NormalMappedMaterial {
+    pub fn with_normal_map(base: Arc<dyn Bsdf + Send + Sync>, normal_map: Arc<Texture>) -> Self {
+        Self {
+            base,
+            normal_map: Some(normal_map),
+            bump_map: None,
+            bump_strength: 0.0,
+        }
+    }
+
+    pub fn with_bump_map(
+        base: Arc<dyn Bsdf + Send + Sync>,
+        bump_map: Arc<Texture>,
+        bump_strength: f32,
+    ) -> Self {
+        Self {
+            base,
+            normal_map: None,
+            bump_map: Some(bump_map),
+            bump_strength,
+        }
+    }
+
+    /// Returns a copy of `hit` with the perturbed shading frame.
+    fn perturb<'a>(&self, hit: &HitRecord<'a>) -> HitRecord<'a> {
+        // Maps are authored for the outward facing frame
+        let sign = if hit.is_front_face { 1.0 } else { -1.0 };
+        let mut normal = hit.normal * sign;
+        let tangent = hit.tangent;
+        let bitangent = normal.cross(&tangent);
+
+        if let Some(normal_map) = &self.normal_map {
+            let texel = normal_map.sample(hit.uv);
+            let local = Vec3::new(
+                2.0 * texel.r() - 1.0,
+                2.0 * texel.g() - 1.0,
+                2.0 * texel.b() - 1.0,
+            );
+            let mapped = tangent * local.x + bitangent * local.y + normal * local.z;
+            if mapped.length_squared() > 0.0 {
+                normal = mapped.normalized();
+            }
+        }
+
+        if let Some(bump_map) = &self.bump_map {
+            let du = 1.0 / bump_map.get_width() as f32;
+            let dv = 1.0 / bump_map.get_height() as f32;
+            let height = bump_map.sample(hit.uv).luminance();
+            let height_u = bump_map.sample(hit.uv + Vec2::new(du, 0.0)).luminance();
+            let height_v = bump_map.sample(hit.uv + Vec2::new(0.0, dv)).luminance();
+
+            let dh_du = (height_u - height) / du;
+            let dh_dv = (height_v - height) / dv;
+            let bumped = normal - (tangent * dh_du + bitangent * dh_dv) * self.bump_strength;
+            if bumped.length_squared() > 0.0 {
+                normal = bumped.normalized();
+            }
+        }
+
+        // Keep the tangent frame orthonormal
+        let tangent = tangent - normal * normal.dot(&tangent);
+        let tangent = if tangent.length_squared() > 1e-12 {
+            tangent.normalized()
+        } else {
+            hit.tangent
+        };
+
+        HitRecord {
+            normal: normal * sign,
+            tangent,
+            ..*hit
+        }
+    }
+}
+
+impl Bsdf for NormalMappedMaterial {
+    fn sample(&self, wo: Vec3, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample> {
+        let perturbed = self.perturb(hit);
+        let sample = self.base.sample(wo, &perturbed, uc, u)?;
+
+        // Reject directions that cross the real surface when the shading frame says they do not
+        let shading_reflect = sample.wi.dot(&perturbed.normal) * wo.dot(&perturbed.normal) > 0.0;
+        let geometric_reflect =
+            sample.wi.dot(&hit.geometric_normal) * wo.dot(&hit.geometric_normal) > 0.0;
+        if shading_reflect != geometric_reflect {
+            return None;
+        }
+
+        let correction = cosine_correction(sample.wi, &perturbed, hit);
+        if correction == 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            f: sample.f * correction,
+            ..sample
+        })
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> Color {
+        let perturbed = self.perturb(hit);
+        self.base.eval(wo, wi, &perturbed) * cosine_correction(wi, &perturbed, hit)
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> f32 {
+        self.base.pdf(wo, wi, &self.perturb(hit))
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        self.base.emitted(&self.perturb(hit))
+    }
+
+    fn opacity(&self, hit: &HitRecord) -> f32 {
+        self.base.opacity(hit)
+    }
+}
+
+/// Ratio of the cosines of `wi` with the perturbed and the original normal.
+///
+/// The base material is evaluated in the perturbed frame, but the integrator multiplies
+/// by the cosine with the normal of the `hit`, so the value is rescaled to the perturbed cosine.
+fn cosine_correction(wi: Vec3, perturbed: &HitRecord, hit: &HitRecord) -> f32 {
+    let cos_original = wi.dot(&hit.normal).abs();
+    if cos_original == 0.0 {
+        return 0.0;
+    }
+    wi.dot(&perturbed.normal).abs() / cos_original
+}
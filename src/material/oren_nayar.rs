@@ -0,0 +1,86 @@
+use std::f32::consts::FRAC_1_PI;
+
+use crate::color::Color;
+use crate::material::BsdfSample;
+use crate::material::microfacet::sample_cosine_hemisphere;
+use crate::math::{Onb, Vec2, Vec3};
+use crate::primitives::HitRecord;
+
+/// Oren-Nayar rough diffuse reflection.
+///
+/// Models retro-reflection of rough surfaces like clay, concrete or the moon.
+/// With zero roughness it is equivalent to the Lambertian material.
+#[derive(Clone, Copy, Debug)]
+pub struct OrenNayarMaterial {
+    pub albedo: Color,
+    /// Standard deviation of the microfacet orientation angle in radians.
+    pub roughness: f32,
+}
+
+impl Default for OrenNayarMaterial {
+    fn default() -> Self {
+        Self {
+            albedo: Color::WHITE,
+            roughness: 0.0,
+        }
+    }
+}
+
+impl OrenNayarMaterial {
+    pub fn eval(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> Color {
+        let onb = Onb::from_normal(hit.normal);
+        let wo = onb.to_local(wo.normalized());
+        let wi = onb.to_local(wi.normalized());
+        self.eval_local(wo, wi)
+    }
+
+    pub fn pdf(&self, _wo: Vec3, wi: Vec3, hit: &HitRecord) -> f32 {
+        f32::max(wi.normalized().dot(&hit.normal), 0.0) * FRAC_1_PI
+    }
+
+    pub fn sample(&self, wo: Vec3, hit: &HitRecord, u: Vec2) -> Option<BsdfSample> {
+        let onb = Onb::from_normal(hit.normal);
+        let wo = onb.to_local(wo.normalized());
+        let wi = sample_cosine_hemisphere(u);
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            wi: onb.to_world(wi),
+            f: self.eval_local(wo, wi),
+            pdf: wi.z * FRAC_1_PI,
+            is_specular: false,
+        })
+    }
+
+    fn eval_local(&self, wo: Vec3, wi: Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::BLACK;
+        }
+
+        let sigma2 = self.roughness * self.roughness;
+        let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+        let sin_i = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+        let sin_o = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+
+        // cos(phi_i - phi_o) from the projections onto the tangent plane
+        let max_cos = if sin_i > 1e-4 && sin_o > 1e-4 {
+            let cos_phi = (wi.x * wo.x + wi.y * wo.y) / (sin_i * sin_o);
+            cos_phi.max(0.0)
+        } else {
+            0.0
+        };
+
+        // sin(alpha) * tan(beta), where alpha = max(theta_i, theta_o), beta = min(...)
+        let (sin_alpha, tan_beta) = if wi.z > wo.z {
+            (sin_o, sin_i / wi.z)
+        } else {
+            (sin_i, sin_o / wo.z)
+        };
+
+        self.albedo * (FRAC_1_PI * (a + b * max_cos * sin_alpha * tan_beta))
+    }
+}
@@ -0,0 +1,486 @@
+use std::f32::consts::FRAC_1_PI;
+
+use crate::color::Color;
+use crate::material::BsdfSample;
+use crate::material::microfacet::{
+    Ggx, Gtr1, fresnel_dielectric, reflect, refract, sample_cosine_hemisphere, schlick_weight,
+};
+use crate::math::{Onb, Vec2, Vec3};
+use crate::primitives::HitRecord;
+use crate::sampler::ONE_MINUS_EPSILON;
+
+/// Disney-style principled BSDF.
+///
+/// Combines a Burley diffuse lobe with sheen, a GGX specular lobe,
+/// a GTR1 clearcoat lobe and a rough dielectric transmission lobe.
+/// All parameters except colors and `ior` are expected in `[0.0, 1.0]`.
+#[derive(Clone, Copy, Debug)]
+pub struct PrincipledMaterial {
+    pub base_color: Color,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Specular reflectance at normal incidence, `0.5` maps to 4%.
+    pub specular: f32,
+    /// Tints the dielectric specular towards the base color.
+    pub specular_tint: f32,
+    /// Color of the dielectric specular, replaces the tint when set.
+    pub specular_color: Option<Color>,
+    pub sheen: f32,
+    /// Tints the sheen towards the base color.
+    pub sheen_tint: f32,
+    /// Color of the sheen, replaces the tint when set.
+    pub sheen_color: Option<Color>,
+    pub clearcoat: f32,
+    pub clearcoat_gloss: f32,
+    pub transmission: f32,
+    /// Index of refraction used by the transmission lobe.
+    pub ior: f32,
+    pub emission: Color,
+    /// Coverage of the surface, the rest is cut out like by an alpha mask.
+    pub opacity: f32,
+}
+
+/// Parameters of a Wavefront MTL material, including the PBR extension.
+#[derive(Clone, Copy, Debug)]
+pub struct MtlParams {
+    /// Diffuse color (`Kd`).
+    pub kd: Color,
+    /// Specular color (`Ks`).
+    pub ks: Color,
+    /// Emissive color (`Ke`).
+    pub ke: Color,
+    /// Specular exponent (`Ns`).
+    pub ns: f32,
+    /// Optical density (`Ni`).
+    pub ni: f32,
+    /// Dissolve (`d`, or `1 - Tr`), the coverage of the surface, `1.0` is fully opaque.
+    pub d: f32,
+    /// Illumination model (`illum`), models 4, 6 and 7 refract through the surface.
+    pub illum: u32,
+    /// Transmission filter (`Tf`), the color of light refracted through the surface.
+    pub tf: Color,
+    /// PBR roughness (`Pr`), overrides `ns` if present.
+    pub pr: Option<f32>,
+    /// PBR metallic (`Pm`).
+    pub pm: f32,
+    /// PBR sheen (`Ps`).
+    pub ps: f32,
+    /// PBR clearcoat thickness (`Pc`).
+    pub pc: f32,
+    /// PBR clearcoat roughness (`Pcr`).
+    pub pcr: f32,
+}
+
+/// Parameters of a glTF 2.0 metallic-roughness material
+/// together with the commonly used `KHR_materials_*` extensions.
+#[derive(Clone, Copy, Debug)]
+pub struct GltfPbrParams {
+    pub base_color_factor: Color,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub emissive_factor: Color,
+    /// `KHR_materials_emissive_strength`
+    pub emissive_strength: f32,
+    /// `KHR_materials_ior`
+    pub ior: f32,
+    /// `KHR_materials_transmission`
+    pub transmission_factor: f32,
+    /// `KHR_materials_specular`
+    pub specular_factor: f32,
+    pub specular_color_factor: Color,
+    /// `KHR_materials_clearcoat`
+    pub clearcoat_factor: f32,
+    pub clearcoat_roughness_factor: f32,
+    /// `KHR_materials_sheen`
+    pub sheen_color_factor: Color,
+}
+
+/// Probabilities of sampling each lobe of the principled BSDF.
+#[derive(Clone, Copy, Debug)]
+struct LobePdfs {
+    diffuse: f32,
+    specular: f32,
+    clearcoat: f32,
+    transmission: f32,
+}
+
+impl Default for PrincipledMaterial {
+    fn default() -> Self {
+        Self {
+            base_color: Color::new(0.8, 0.8, 0.8),
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            specular_color: None,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            sheen_color: None,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+            transmission: 0.0,
+            ior: 1.5,
+            emission: Color::BLACK,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Default for MtlParams {
+    fn default() -> Self {
+        Self {
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::BLACK,
+            ke: Color::BLACK,
+            ns: 10.0,
+            ni: 1.5,
+            d: 1.0,
+            illum: 2,
+            tf: Color::WHITE,
+            pr: None,
+            pm: 0.0,
+            ps: 0.0,
+            pc: 0.0,
+            pcr: 0.0,
+        }
+    }
+}
+
+impl Default for GltfPbrParams {
+    fn default() -> Self {
+        Self {
+            base_color_factor: Color::WHITE,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            emissive_factor: Color::BLACK,
+            emissive_strength: 1.0,
+            ior: 1.5,
+            transmission_factor: 0.0,
+            specular_factor: 1.0,
+            specular_color_factor: Color::WHITE,
+            clearcoat_factor: 0.0,
+            clearcoat_roughness_factor: 0.0,
+            sheen_color_factor: Color::BLACK,
+        }
+    }
+}
+
+impl PrincipledMaterial {
+    /// Converts the MTL parameters, a non-black `Ks` gives the specular reflectance
+    /// at normal incidence instead of `Ni`.
+    ///
+    /// The dissolve cuts out the surface, only the refracting illumination models
+    /// turn it into a glass tinted by `Tf` instead of `Kd`.
+    pub fn from_mtl(mtl: &MtlParams) -> Self {
+        // Phong exponent to Beckmann alpha, then alpha to perceptual roughness
+        let roughness = mtl
+            .pr
+            .unwrap_or_else(|| f32::sqrt(f32::sqrt(2.0 / (mtl.ns.max(0.0) + 2.0))));
+        let specular = if mtl.ks.is_black() {
+            specular_from_ior(mtl.ni)
+        } else {
+            (mtl.ks.luminance() / 0.08).clamp(0.0, 1.0)
+        };
+        let is_refractive = matches!(mtl.illum, 4 | 6 | 7);
+
+        Self {
+            base_color: if is_refractive { mtl.tf } else { mtl.kd },
+            metallic: mtl.pm,
+            roughness,
+            specular,
+            specular_tint: 0.0,
+            specular_color: None,
+            sheen: mtl.ps,
+            sheen_tint: 0.5,
+            sheen_color: None,
+            clearcoat: mtl.pc,
+            clearcoat_gloss: 1.0 - mtl.pcr,
+            transmission: if is_refractive { 1.0 } else { 0.0 },
+            ior: mtl.ni,
+            emission: mtl.ke,
+            opacity: mtl.d.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn from_gltf(gltf: &GltfPbrParams) -> Self {
+        // The luminance of the sheen color is the strength, the rest is its hue
+        let sheen = gltf.sheen_color_factor.luminance();
+        let sheen_color = (sheen > 0.0).then(|| gltf.sheen_color_factor * sheen.recip());
+
+        Self {
+            base_color: gltf.base_color_factor,
+            metallic: gltf.metallic_factor,
+            roughness: gltf.roughness_factor,
+            specular: specular_from_ior(gltf.ior) * gltf.specular_factor,
+            specular_tint: 0.0,
+            specular_color: Some(gltf.specular_color_factor),
+            sheen,
+            sheen_tint: 0.0,
+            sheen_color,
+            clearcoat: gltf.clearcoat_factor,
+            clearcoat_gloss: 1.0 - gltf.clearcoat_roughness_factor,
+            transmission: gltf.transmission_factor,
+            ior: gltf.ior,
+            emission: gltf.emissive_factor * gltf.emissive_strength,
+            opacity: 1.0,
+        }
+    }
+
+    /// Evaluates the BSDF for the outgoing direction `wo`
+    /// and incident direction `wi`, both pointing away from the surface.
+    pub fn eval(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> Color {
+        let onb = Onb::from_normal(hit.normal);
+        let wo = onb.to_local(wo.normalized());
+        let wi = onb.to_local(wi.normalized());
+        self.eval_local(wo, wi, self.relative_ior(hit))
+    }
+
+    /// Returns the solid angle density of sampling `wi` given `wo`.
+    pub fn pdf(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> f32 {
+        let onb = Onb::from_normal(hit.normal);
+        let wo = onb.to_local(wo.normalized());
+        let wi = onb.to_local(wi.normalized());
+        self.pdf_local(wo, wi, self.relative_ior(hit))
+    }
+
+    /// Samples an incident direction for the outgoing direction `wo`,
+    /// `uc` chooses the lobe and `u` the direction within it.
+    pub fn sample(&self, wo: Vec3, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample> {
+        let onb = Onb::from_normal(hit.normal);
+        let wo = onb.to_local(wo.normalized());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let eta = self.relative_ior(hit);
+        let pdfs = self.lobe_pdfs();
+        let transmission_start = pdfs.diffuse + pdfs.specular + pdfs.clearcoat;
+
+        let wi = if uc < pdfs.diffuse {
+            sample_cosine_hemisphere(u)
+        } else if uc < pdfs.diffuse + pdfs.specular {
+            let wm = self.specular_distribution().sample_wm(wo, u);
+            reflect(wo, wm)
+        } else if uc < transmission_start {
+            let wm = self.clearcoat_distribution().sample_wm(u);
+            reflect(wo, wm)
+        } else {
+            let wm = self.specular_distribution().sample_wm(wo, u);
+            let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+            // Reuses the lobe sample remapped to `[0.0, 1.0)` to choose reflection
+            let uc = ((uc - transmission_start) / pdfs.transmission).min(ONE_MINUS_EPSILON);
+            if uc < reflectance {
+                reflect(wo, wm)
+            } else {
+                refract(wo, wm, eta)?
+            }
+        };
+
+        if wi.z == 0.0 {
+            return None;
+        }
+
+        let pdf = self.pdf_local(wo, wi, eta);
+        if pdf <= 0.0 || !pdf.is_finite() {
+            return None;
+        }
+
+        Some(BsdfSample {
+            wi: onb.to_world(wi),
+            f: self.eval_local(wo, wi, eta),
+            pdf,
+            is_specular: false,
+        })
+    }
+
+    fn relative_ior(&self, hit: &HitRecord) -> f32 {
+        if hit.is_front_face {
+            self.ior
+        } else {
+            self.ior.recip()
+        }
+    }
+
+    fn specular_distribution(&self) -> Ggx {
+        Ggx::from_roughness(self.roughness)
+    }
+
+    fn clearcoat_distribution(&self) -> Gtr1 {
+        let gloss = self.clearcoat_gloss.clamp(0.0, 1.0);
+        Gtr1::new(0.1 * (1.0 - gloss) + 0.001 * gloss)
+    }
+
+    fn diffuse_weight(&self) -> f32 {
+        (1.0 - self.metallic) * (1.0 - self.transmission)
+    }
+
+    fn transmission_weight(&self) -> f32 {
+        (1.0 - self.metallic) * self.transmission
+    }
+
+    fn lobe_pdfs(&self) -> LobePdfs {
+        let diffuse = self.diffuse_weight();
+        let transmission = self.transmission_weight();
+        let specular = 1.0 - transmission;
+        let clearcoat = 0.25 * self.clearcoat;
+
+        let total = diffuse + specular + clearcoat + transmission;
+        LobePdfs {
+            diffuse: diffuse / total,
+            specular: specular / total,
+            clearcoat: clearcoat / total,
+            transmission: transmission / total,
+        }
+    }
+
+    fn tint(&self) -> Color {
+        let luminance = self.base_color.luminance();
+        if luminance > 0.0 {
+            self.base_color * luminance.recip()
+        } else {
+            Color::WHITE
+        }
+    }
+
+    fn eval_local(&self, wo: Vec3, wi: Vec3, eta: f32) -> Color {
+        let cos_o = wo.z;
+        let cos_i = wi.z;
+        if cos_o <= 0.0 || cos_i == 0.0 {
+            return Color::BLACK;
+        }
+
+        let transmission_weight = self.transmission_weight();
+        if cos_i < 0.0 {
+            if transmission_weight <= 0.0 {
+                return Color::BLACK;
+            }
+            return self.eval_transmission(wo, wi, eta) * transmission_weight;
+        }
+
+        let wm = (wo + wi).normalized();
+        let cos_d = wi.dot(&wm);
+        let mut f = Color::BLACK;
+
+        let diffuse_weight = self.diffuse_weight();
+        if diffuse_weight > 0.0 {
+            let fl = schlick_weight(cos_i);
+            let fv = schlick_weight(cos_o);
+            let fd90 = 0.5 + 2.0 * cos_d * cos_d * self.roughness;
+            let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+            let diffuse = self.base_color * (fd * FRAC_1_PI);
+
+            let sheen_color = self
+                .sheen_color
+                .unwrap_or_else(|| Color::WHITE.lerp(self.tint(), self.sheen_tint));
+            let sheen = sheen_color * (self.sheen * schlick_weight(cos_d));
+
+            f += (diffuse + sheen) * diffuse_weight;
+        }
+
+        let specular_weight = 1.0 - transmission_weight;
+        if specular_weight > 0.0 {
+            let distribution = self.specular_distribution();
+            let specular_color = self
+                .specular_color
+                .unwrap_or_else(|| Color::WHITE.lerp(self.tint(), self.specular_tint));
+            let dielectric_spec = specular_color * (0.08 * self.specular);
+            let spec0 = dielectric_spec.lerp(self.base_color, self.metallic.clamp(0.0, 1.0));
+            let fresnel = spec0.lerp(Color::WHITE, schlick_weight(cos_d));
+            let dg = distribution.d(wm) * distribution.g(wo, wi);
+            f += fresnel * (specular_weight * dg / (4.0 * cos_i * cos_o));
+        }
+
+        if self.clearcoat > 0.0 {
+            let distribution = self.clearcoat_distribution();
+            let fresnel = 0.04 + 0.96 * schlick_weight(cos_d);
+            let masking = Ggx::new(0.25);
+            let g = masking.g1(wo) * masking.g1(wi);
+            let cc = 0.25 * self.clearcoat * distribution.d(wm) * fresnel * g;
+            f += Color::WHITE * (cc / (4.0 * cos_i * cos_o));
+        }
+
+        if transmission_weight > 0.0 {
+            let distribution = self.specular_distribution();
+            let fresnel = fresnel_dielectric(wo.dot(&wm), eta);
+            let dg = distribution.d(wm) * distribution.g(wo, wi);
+            f += Color::WHITE * (transmission_weight * fresnel * dg / (4.0 * cos_i * cos_o));
+        }
+
+        f
+    }
+
+    fn eval_transmission(&self, wo: Vec3, wi: Vec3, eta: f32) -> Color {
+        let Some((wm, denom)) = transmission_half_vector(wo, wi, eta) else {
+            return Color::BLACK;
+        };
+
+        let distribution = self.specular_distribution();
+        let fresnel = fresnel_dielectric(wo.dot(&wm), eta);
+        let ft = distribution.d(wm)
+            * (1.0 - fresnel)
+            * distribution.g(wo, wi)
+            * f32::abs(wi.dot(&wm) * wo.dot(&wm) / (denom * wi.z * wo.z));
+
+        // Radiance is compressed when entering a denser medium
+        self.base_color * (ft / (eta * eta))
+    }
+
+    fn pdf_local(&self, wo: Vec3, wi: Vec3, eta: f32) -> f32 {
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return 0.0;
+        }
+
+        let pdfs = self.lobe_pdfs();
+        let distribution = self.specular_distribution();
+
+        if wi.z < 0.0 {
+            if pdfs.transmission <= 0.0 {
+                return 0.0;
+            }
+            let Some((wm, denom)) = transmission_half_vector(wo, wi, eta) else {
+                return 0.0;
+            };
+            let fresnel = fresnel_dielectric(wo.dot(&wm), eta);
+            let dwm_dwi = wi.dot(&wm).abs() / denom;
+            return pdfs.transmission * (1.0 - fresnel) * distribution.pdf(wo, wm) * dwm_dwi;
+        }
+
+        let wm = (wo + wi).normalized();
+        let reflect_jacobian = 1.0 / (4.0 * wo.dot(&wm).abs());
+        let mut pdf = pdfs.diffuse * wi.z * FRAC_1_PI;
+        pdf += pdfs.specular * distribution.pdf(wo, wm) * reflect_jacobian;
+        pdf += pdfs.clearcoat * self.clearcoat_distribution().pdf(wm) * reflect_jacobian;
+        if pdfs.transmission > 0.0 {
+            let fresnel = fresnel_dielectric(wo.dot(&wm), eta);
+            pdf += pdfs.transmission * fresnel * distribution.pdf(wo, wm) * reflect_jacobian;
+        }
+        pdf
+    }
+}
+
+/// Generalized half vector of a refraction from `wo` into `wi`
+/// together with the squared denominator of the change of variables.
+fn transmission_half_vector(wo: Vec3, wi: Vec3, eta: f32) -> Option<(Vec3, f32)> {
+    let wm = wi * eta + wo;
+    if wm.length_squared() == 0.0 {
+        return None;
+    }
+    let mut wm = wm.normalized();
+    if wm.z < 0.0 {
+        wm = -wm;
+    }
+
+    // Discard back facing microfacets
+    if wm.dot(&wi) * wi.z < 0.0 || wm.dot(&wo) * wo.z < 0.0 {
+        return None;
+    }
+
+    let denom = wi.dot(&wm) + wo.dot(&wm) / eta;
+    Some((wm, denom * denom))
+}
+
+/// Maps the index of refraction to the principled `specular` parameter.
+fn specular_from_ior(ior: f32) -> f32 {
+    let f0 = (ior - 1.0) / (ior + 1.0);
+    (f0 * f0 / 0.08).clamp(0.0, 1.0)
+}
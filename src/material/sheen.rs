@@ -0,0 +1,186 @@
+use std::f32::consts::{FRAC_1_PI, PI};
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::material::microfacet::sample_cosine_hemisphere;
+use crate::material::{Bsdf, BsdfSample};
+use crate::math::{Onb, Vec2, Vec3};
+use crate::primitives::HitRecord;
+use crate::sampler::ONE_MINUS_EPSILON;
+
+/// Resolution of the tabulated directional albedo of the sheen lobe.
+const ALBEDO_TABLE_SIZE: usize = 32;
+
+/// Charlie sheen lobe (Estevez and Kulla) for cloth-like surfaces.
+///
+/// Can be used alone or layered over a base material,
+/// in which case the base is scaled by the energy not reflected by the sheen.
+#[derive(Clone, Debug)]
+pub struct SheenMaterial {
+    color: Color,
+    roughness: f32,
+    base: Option<Arc<dyn Bsdf + Send + Sync>>,
+    albedo: [f32; ALBEDO_TABLE_SIZE],
+}
+
+impl SheenMaterial {
+    /// Creates a standalone sheen lobe, `roughness` is in `[0.0, 1.0]`.
+    pub fn new(color: Color, roughness: f32) -> Self {
+        let mut sheen = Self {
+            color,
+            roughness,
+            base: None,
+            albedo: [0.0; ALBEDO_TABLE_SIZE],
+        };
+        sheen.albedo = std::array::from_fn(|i| {
+            let cos_o = (i as f32 + 0.5) / ALBEDO_TABLE_SIZE as f32;
+            sheen.directional_albedo(cos_o)
+        });
+        sheen
+    }
+
+    /// Creates a sheen lobe layered over `base`.
+    pub fn layered(color: Color, roughness: f32, base: Arc<dyn Bsdf + Send + Sync>) -> Self {
+        Self {
+            base: Some(base),
+            ..Self::new(color, roughness)
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn roughness(&self) -> f32 {
+        self.roughness
+    }
+
+    fn alpha(&self) -> f32 {
+        let roughness = self.roughness.clamp(0.0, 1.0);
+        (roughness * roughness).max(0.07)
+    }
+
+    /// Charlie microfacet distribution.
+    fn d(&self, wm: Vec3) -> f32 {
+        let inv_alpha = self.alpha().recip();
+        let sin_theta = (1.0 - wm.z * wm.z).max(0.0).sqrt();
+        (2.0 + inv_alpha) * sin_theta.powf(inv_alpha) / (2.0 * PI)
+    }
+
+    /// Visibility term approximation by Neubelt and Pettineo.
+    fn v(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (4.0 * (wi.z + wo.z - wi.z * wo.z))
+    }
+
+    fn eval_sheen(&self, wo: Vec3, wi: Vec3) -> f32 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).normalized();
+        self.d(wm) * self.v(wo, wi)
+    }
+
+    /// Integrates the reflectance of a white sheen lobe over the hemisphere.
+    fn directional_albedo(&self, cos_o: f32) -> f32 {
+        const N: usize = 32;
+        let wo = Vec3::new((1.0 - cos_o * cos_o).max(0.0).sqrt(), 0.0, cos_o);
+        let mut albedo = 0.0;
+        for i in 0..N {
+            for j in 0..N {
+                let u = Vec2::new((i as f32 + 0.5) / N as f32, (j as f32 + 0.5) / N as f32);
+                let wi = sample_cosine_hemisphere(u);
+                // Cosine cancels with the cosine weighted density
+                albedo += self.eval_sheen(wo, wi) * PI;
+            }
+        }
+        (albedo / (N * N) as f32).min(1.0)
+    }
+
+    /// Fraction of energy reflected by the sheen lobe towards `wo`.
+    fn reflectance(&self, cos_o: f32) -> f32 {
+        let index = (cos_o.clamp(0.0, 1.0) * ALBEDO_TABLE_SIZE as f32) as usize;
+        let albedo = self.albedo[index.min(ALBEDO_TABLE_SIZE - 1)];
+        let max_color = self.color.r().max(self.color.g()).max(self.color.b());
+        (albedo * max_color).clamp(0.0, 1.0)
+    }
+}
+
+impl Bsdf for SheenMaterial {
+    fn sample(&self, wo: Vec3, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample> {
+        let onb = Onb::from_normal(hit.normal);
+        let wo_world = wo.normalized();
+        let wo = onb.to_local(wo_world);
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let sheen_probability = match self.base {
+            Some(_) => self.reflectance(wo.z),
+            None => 1.0,
+        };
+
+        let wi = if uc < sheen_probability {
+            onb.to_world(sample_cosine_hemisphere(u))
+        } else {
+            let base = self.base.as_ref()?;
+            // Reuses the lobe sample remapped to `[0.0, 1.0)` for the base
+            let uc = ((uc - sheen_probability) / (1.0 - sheen_probability)).min(ONE_MINUS_EPSILON);
+            let sample = base.sample(wo_world, hit, uc, u)?;
+            if sample.is_specular {
+                // Sheen reflectance cancels with the probability of sampling the base
+                return Some(sample);
+            }
+            sample.wi
+        };
+
+        let pdf = self.pdf(wo_world, wi, hit);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            wi,
+            f: self.eval(wo_world, wi, hit),
+            pdf,
+            is_specular: false,
+        })
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> Color {
+        let onb = Onb::from_normal(hit.normal);
+        let wo_local = onb.to_local(wo.normalized());
+        let wi_local = onb.to_local(wi.normalized());
+
+        let sheen = self.color * self.eval_sheen(wo_local, wi_local);
+        match &self.base {
+            Some(base) => sheen + base.eval(wo, wi, hit) * (1.0 - self.reflectance(wo_local.z)),
+            None => sheen,
+        }
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, hit: &HitRecord) -> f32 {
+        let cos_i = wi.normalized().dot(&hit.normal);
+        let sheen_pdf = cos_i.max(0.0) * FRAC_1_PI;
+        match &self.base {
+            Some(base) => {
+                let sheen_probability = self.reflectance(wo.normalized().dot(&hit.normal));
+                sheen_probability * sheen_pdf + (1.0 - sheen_probability) * base.pdf(wo, wi, hit)
+            }
+            None => sheen_pdf,
+        }
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        match &self.base {
+            Some(base) => base.emitted(hit),
+            None => Color::BLACK,
+        }
+    }
+
+    fn opacity(&self, hit: &HitRecord) -> f32 {
+        match &self.base {
+            Some(base) => base.opacity(hit),
+            None => 1.0,
+        }
+    }
+}
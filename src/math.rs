@@ -1,13 +1,17 @@
 mod aabb;
+mod distribution;
 mod interval;
 mod mat3;
+mod onb;
 mod transform;
 mod vec2;
 mod vec3;
 
 pub use aabb::*;
+pub use distribution::*;
 pub use interval::*;
 pub use mat3::*;
+pub use onb::*;
 pub use transform::*;
 pub use vec2::*;
 pub use vec3::*;
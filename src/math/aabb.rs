@@ -56,6 +56,29 @@ impl Aabb {
         )
     }
 
+    pub fn min_corner(&self) -> Vec3 {
+        Vec3::new(self.x_axis.min, self.y_axis.min, self.z_axis.min)
+    }
+
+    pub fn max_corner(&self) -> Vec3 {
+        Vec3::new(self.x_axis.max, self.y_axis.max, self.z_axis.max)
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min_corner() + self.max_corner()) * 0.5
+    }
+
+    /// Vector from the minimum to the maximum corner.
+    pub fn diagonal(&self) -> Vec3 {
+        self.max_corner() - self.min_corner()
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.x_axis.contains(point.x)
+            && self.y_axis.contains(point.y)
+            && self.z_axis.contains(point.z)
+    }
+
     pub fn longest_axis(&self) -> Axis {
         if self.x_axis.length() > self.y_axis.length() {
             if self.x_axis.length() > self.z_axis.length() {
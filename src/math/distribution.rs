@@ -0,0 +1,237 @@
+/// Piecewise-constant 1D distribution over `[0.0, 1.0)`.
+#[derive(Clone, Debug)]
+pub struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    func_int: f32,
+}
+
+impl Distribution1D {
+    /// Creates a distribution proportional to the non-negative `func`.
+    pub fn new(func: Vec<f32>) -> Self {
+        assert!(!func.is_empty(), "Distribution must not be empty");
+        let n = func.len();
+        let func: Vec<f32> = func.into_iter().map(|value| value.abs()).collect();
+
+        let mut cdf = Vec::with_capacity(n + 1);
+        cdf.push(0.0);
+        for i in 0..n {
+            cdf.push(cdf[i] + func[i] / n as f32);
+        }
+
+        let func_int = cdf[n];
+        if func_int == 0.0 {
+            // Fall back to uniform distribution
+            for (i, value) in cdf.iter_mut().enumerate() {
+                *value = i as f32 / n as f32;
+            }
+        } else {
+            for value in cdf.iter_mut() {
+                *value /= func_int;
+            }
+        }
+
+        Self {
+            func,
+            cdf,
+            func_int,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    /// Integral of the function over `[0.0, 1.0)`.
+    pub fn integral(&self) -> f32 {
+        self.func_int
+    }
+
+    /// Maps uniform `u` to `(x, pdf, index)` where `x` lies in the bucket `index`.
+    pub fn sample_continuous(&self, u: f32) -> (f32, f32, usize) {
+        // Last cdf entry not greater than u
+        let index = self
+            .cdf
+            .partition_point(|&value| value <= u)
+            .saturating_sub(1)
+            .min(self.len() - 1);
+
+        let mut du = u - self.cdf[index];
+        let width = self.cdf[index + 1] - self.cdf[index];
+        if width > 0.0 {
+            du /= width;
+        }
+
+        let x = (index as f32 + du.clamp(0.0, 1.0)) / self.len() as f32;
+        (x.min(1.0 - f32::EPSILON), self.pdf_at(index), index)
+    }
+
+    /// Maps uniform `u` to the index of a bucket and its probability.
+    pub fn sample_discrete(&self, u: f32) -> (usize, f32) {
+        let (_, _, index) = self.sample_continuous(u);
+        (index, self.pmf(index))
+    }
+
+    /// Density at `x` in `[0.0, 1.0)`.
+    pub fn pdf(&self, x: f32) -> f32 {
+        let index = ((x * self.len() as f32) as usize).min(self.len() - 1);
+        self.pdf_at(index)
+    }
+
+    /// Probability of choosing the bucket `index` by [`Self::sample_discrete`].
+    pub fn pmf(&self, index: usize) -> f32 {
+        self.cdf[index + 1] - self.cdf[index]
+    }
+
+    fn pdf_at(&self, index: usize) -> f32 {
+        if self.func_int > 0.0 {
+            self.func[index] / self.func_int
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Piecewise-constant 2D distribution over `[0.0, 1.0)^2`.
+#[derive(Clone, Debug)]
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// Creates a distribution from `func` stored row by row, `width` values per row.
+    pub fn new(func: &[f32], width: usize, height: usize) -> Self {
+        assert_eq!(func.len(), width * height, "Size of func is incorrect");
+        let conditional: Vec<_> = func
+            .chunks_exact(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|row| row.integral()).collect());
+
+        Self {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// Maps uniform `(u0, u1)` to a point `(x, y)` and its density.
+    pub fn sample_continuous(&self, u0: f32, u1: f32) -> ((f32, f32), f32) {
+        let (y, pdf_y, row) = self.marginal.sample_continuous(u1);
+        let (x, pdf_x, _) = self.conditional[row].sample_continuous(u0);
+        ((x, y), pdf_x * pdf_y)
+    }
+
+    /// Density at the point `(x, y)`.
+    pub fn pdf(&self, x: f32, y: f32) -> f32 {
+        let row = ((y * self.marginal.len() as f32) as usize).min(self.marginal.len() - 1);
+        self.marginal.pdf(y) * self.conditional[row].pdf(x)
+    }
+}
+
+/// Discrete distribution sampled in constant time by Walker's alias method.
+#[derive(Clone, Debug)]
+pub struct AliasTable {
+    bins: Vec<AliasBin>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct AliasBin {
+    /// Probability of keeping the bin instead of jumping to its alias.
+    threshold: f32,
+    pmf: f32,
+    alias: usize,
+}
+
+impl AliasTable {
+    /// Creates a distribution proportional to the non-negative `weights`.
+    pub fn new(weights: &[f32]) -> Self {
+        assert!(!weights.is_empty(), "Distribution must not be empty");
+        let n = weights.len();
+        let sum: f64 = weights.iter().map(|&weight| weight.abs() as f64).sum();
+        let pmf = |weight: f32| {
+            if sum > 0.0 {
+                (weight.abs() as f64 / sum) as f32
+            } else {
+                // Fall back to uniform distribution
+                (n as f32).recip()
+            }
+        };
+
+        let mut bins: Vec<_> = weights
+            .iter()
+            .map(|&weight| AliasBin {
+                threshold: 0.0,
+                pmf: pmf(weight),
+                alias: 0,
+            })
+            .collect();
+
+        // Probabilities scaled by the number of bins, split into the ones below and above average
+        let mut under = Vec::new();
+        let mut over = Vec::new();
+        for (i, bin) in bins.iter().enumerate() {
+            let scaled = bin.pmf as f64 * n as f64;
+            if scaled < 1.0 {
+                under.push((i, scaled));
+            } else {
+                over.push((i, scaled));
+            }
+        }
+
+        while let (Some(&(small, small_p)), Some(&(large, large_p))) = (under.last(), over.last()) {
+            under.pop();
+            over.pop();
+            bins[small].threshold = small_p as f32;
+            bins[small].alias = large;
+
+            // Excess of the large bin not covered by the small one
+            let excess = large_p - (1.0 - small_p);
+            if excess < 1.0 {
+                under.push((large, excess));
+            } else {
+                over.push((large, excess));
+            }
+        }
+
+        // Remaining bins are full up to rounding errors
+        for (i, _) in under.into_iter().chain(over) {
+            bins[i].threshold = 1.0;
+            bins[i].alias = i;
+        }
+
+        Self { bins }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bins.is_empty()
+    }
+
+    /// Maps uniform `u` to an index and its probability.
+    pub fn sample(&self, u: f32) -> (usize, f32) {
+        let scaled = u * self.len() as f32;
+        let offset = (scaled as usize).min(self.len() - 1);
+        let up = (scaled - offset as f32).min(1.0 - f32::EPSILON);
+
+        let bin = &self.bins[offset];
+        let index = if up < bin.threshold {
+            offset
+        } else {
+            bin.alias
+        };
+        (index, self.bins[index].pmf)
+    }
+
+    /// Probability of sampling `index`.
+    pub fn pmf(&self, index: usize) -> f32 {
+        self.bins[index].pmf
+    }
+}
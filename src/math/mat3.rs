@@ -27,6 +27,21 @@ impl Mat3 {
             z_axis,
         }
     }
+
+    /// Rotation around the Y axis by `angle` in radians.
+    pub fn from_rotation_y(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::from_cols(Vec3::new(cos, 0.0, -sin), Vec3::Y, Vec3::new(sin, 0.0, cos))
+    }
+
+    /// Returns the transposed matrix, which is the inverse for rotations.
+    pub fn transpose(&self) -> Self {
+        Self::from_cols(
+            Vec3::new(self.x_axis.x, self.y_axis.x, self.z_axis.x),
+            Vec3::new(self.x_axis.y, self.y_axis.y, self.z_axis.y),
+            Vec3::new(self.x_axis.z, self.y_axis.z, self.z_axis.z),
+        )
+    }
 }
 
 impl Mul<Vec3> for Mat3 {
@@ -0,0 +1,40 @@
+use crate::math::Vec3;
+
+/// Orthonormal basis with `w` aligned to the given normal.
+#[derive(Clone, Copy, Debug)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    /// Builds a basis around the unit vector `normal`
+    /// (Duff et al., "Building an Orthonormal Basis, Revisited").
+    #[inline]
+    pub fn from_normal(normal: Vec3) -> Self {
+        debug_assert!(normal.is_normalized());
+        let sign = f32::copysign(1.0, normal.z);
+        let a = -1.0 / (sign + normal.z);
+        let b = normal.x * normal.y * a;
+        let u = Vec3::new(
+            1.0 + sign * normal.x * normal.x * a,
+            sign * b,
+            -sign * normal.x,
+        );
+        let v = Vec3::new(b, sign + normal.y * normal.y * a, -normal.y);
+        Self { u, v, w: normal }
+    }
+
+    /// Transforms a world space vector into the local space of the basis.
+    #[inline]
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.u), v.dot(&self.v), v.dot(&self.w))
+    }
+
+    /// Transforms a local space vector into the world space.
+    #[inline]
+    pub fn to_world(&self, v: Vec3) -> Vec3 {
+        self.u * v.x + self.v * v.y + self.w * v.z
+    }
+}
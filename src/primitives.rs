@@ -1,10 +1,13 @@
+use std::f32::consts::PI;
 use std::sync::Arc;
 
-use crate::material::Material;
-use crate::math::{Aabb, Axis, Interval, Vec3};
+use crate::light::Light;
+use crate::material::Bsdf;
+use crate::math::{Aabb, Axis, Interval, Vec2, Vec3};
+use crate::sampler::hash;
 
 pub trait Hittable {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord<'_>>;
 
     fn bounding_box(&self) -> Aabb;
 }
@@ -56,7 +59,8 @@ impl BvhNode {
 }
 
 impl Hittable for BvhNode {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let t_range = ray.clip_range(t_range);
         if !self.bbox.hit(ray, t_range) {
             return None;
         }
@@ -77,23 +81,102 @@ impl Hittable for BvhNode {
 }
 
 #[derive(Clone, Copy, Debug)]
-pub struct HitRecord {
+pub struct HitRecord<'a> {
     pub point: Vec3,
+    /// Shading normal facing against the ray, may be perturbed by the material.
     pub normal: Vec3,
+    /// True surface normal facing against the ray.
+    pub geometric_normal: Vec3,
+    /// Unit tangent along the `u` texture direction, perpendicular to `normal`.
+    pub tangent: Vec3,
+    /// Surface texture coordinates.
+    pub uv: Vec2,
     pub t: f32,
+    /// Time of the ray which hit the surface.
+    pub time: f32,
     pub is_front_face: bool,
-    pub material: Material,
+    pub material: &'a dyn Bsdf,
+    /// Light the surface belongs to, its emission is also sampled by next-event estimation.
+    pub light: Option<&'a dyn Light>,
+}
+
+impl HitRecord<'_> {
+    /// Offset that moves ray origins off the surface to avoid self-intersection.
+    const RAY_OFFSET: f32 = 1e-4;
+
+    /// Unit bitangent along the `v` texture direction.
+    #[inline]
+    pub fn bitangent(&self) -> Vec3 {
+        self.normal.cross(&self.tangent)
+    }
+
+    /// Performs the alpha test of the material, stochastic for partially transparent surfaces.
+    ///
+    /// The random decision is a hash of the `ray` and the hit distance, so it is the same
+    /// in every render and independent for each surface along the ray.
+    #[inline]
+    pub fn is_opaque(&self, ray: &Ray) -> bool {
+        let opacity = self.material.opacity(self);
+        if opacity >= 1.0 {
+            return true;
+        }
+        if opacity <= 0.0 {
+            return false;
+        }
+
+        let (origin, dir) = (ray.origin(), ray.direction());
+        let hash = hash(&[
+            origin.x.to_bits() as u64,
+            origin.y.to_bits() as u64,
+            origin.z.to_bits() as u64,
+            dir.x.to_bits() as u64,
+            dir.y.to_bits() as u64,
+            dir.z.to_bits() as u64,
+            self.t.to_bits() as u64,
+        ]);
+        // Upper 24 bits give a uniform value in `[0.0, 1.0)`
+        ((hash >> 40) as f32 / (1 << 24) as f32) < opacity
+    }
+
+    /// Spawns a ray leaving the surface in the direction `dir`.
+    #[inline]
+    pub fn spawn_ray(&self, dir: Vec3) -> Ray {
+        let offset = if dir.dot(&self.geometric_normal) > 0.0 {
+            self.geometric_normal * Self::RAY_OFFSET
+        } else {
+            -self.geometric_normal * Self::RAY_OFFSET
+        };
+        Ray::with_time(self.point + offset, dir, self.time)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct Ray {
     origin: Vec3,
     dir: Vec3,
+    time: f32,
+    t_max: f32,
 }
 
 impl Ray {
     pub const fn new(origin: Vec3, dir: Vec3) -> Self {
-        Self { origin, dir }
+        Self::with_time(origin, dir, 0.0)
+    }
+
+    /// Creates a ray at the `time` in seconds within the camera shutter interval.
+    pub const fn with_time(origin: Vec3, dir: Vec3, time: f32) -> Self {
+        Self {
+            origin,
+            dir,
+            time,
+            t_max: f32::INFINITY,
+        }
+    }
+
+    /// Limits the ray to the distance `t_max` along its direction.
+    pub const fn with_t_max(mut self, t_max: f32) -> Self {
+        self.t_max = t_max;
+        self
     }
 
     pub fn origin(&self) -> Vec3 {
@@ -104,11 +187,26 @@ impl Ray {
         self.dir
     }
 
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// The farthest distance along the ray, intersections beyond it are ignored.
+    pub fn t_max(&self) -> f32 {
+        self.t_max
+    }
+
+    /// Limits `t_range` to [`Self::t_max`], hittables intersect the ray only within the result.
+    #[inline]
+    pub fn clip_range(&self, t_range: Interval) -> Interval {
+        Interval::new(t_range.min, t_range.max.min(self.t_max))
+    }
+
     pub fn at(&self, t: f32) -> Vec3 {
         self.origin + self.dir * t
     }
 
-    pub fn hit(&self, object: &impl Hittable, t_range: Interval) -> Option<HitRecord> {
+    pub fn hit<'a>(&self, object: &'a impl Hittable, t_range: Interval) -> Option<HitRecord<'a>> {
         object.hit(self, t_range)
     }
 }
@@ -127,7 +225,8 @@ impl HittableList {
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let t_range = ray.clip_range(t_range);
         self.objects
             .iter()
             .filter_map(|object| object.hit(ray, t_range))
@@ -139,15 +238,16 @@ impl Hittable for HittableList {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Sphere {
     pub center: Vec3,
     pub radius: f32,
-    pub material: Material,
+    pub material: Arc<dyn Bsdf + Send + Sync>,
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let t_range = ray.clip_range(t_range);
         let oc = self.center - ray.origin();
 
         let a = ray.direction().length_squared();
@@ -161,14 +261,27 @@ impl Hittable for Sphere {
         }
 
         let dsqrt = discriminant.sqrt();
-        let mut t = (h - dsqrt) / a;
-        if !t_range.contains(t) {
-            t = (h + dsqrt) / a;
+        for t in [(h - dsqrt) / a, (h + dsqrt) / a] {
             if !t_range.contains(t) {
-                return None;
+                continue;
+            }
+
+            let hit = self.hit_record(ray, t);
+            if hit.is_opaque(ray) {
+                return Some(hit);
             }
         }
 
+        None
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_center(self.center, Vec3::splat(self.radius))
+    }
+}
+
+impl Sphere {
+    fn hit_record(&self, ray: &Ray, t: f32) -> HitRecord<'_> {
         let point = ray.at(t);
         let out_normal = (point - self.center) / self.radius;
         // This can be slightly of due to floating errors
@@ -180,16 +293,130 @@ impl Hittable for Sphere {
         } else {
             -out_normal
         };
-        Some(HitRecord {
+
+        let (uv, tangent) = Self::parameterization(out_normal);
+        HitRecord {
+            point,
+            normal,
+            geometric_normal: normal,
+            tangent,
+            uv,
+            t,
+            time: ray.time(),
+            is_front_face,
+            material: self.material.as_ref(),
+            light: None,
+        }
+    }
+
+    /// Returns texture coordinates and the `u` tangent at the point with `normal`.
+    ///
+    /// `u` goes around the Y axis starting at `-X`, `v` goes from the bottom pole to the top one.
+    fn parameterization(normal: Vec3) -> (Vec2, Vec3) {
+        let theta = f32::acos((-normal.y).clamp(-1.0, 1.0));
+        let phi = f32::atan2(-normal.z, normal.x) + PI;
+        let uv = Vec2::new(phi / (2.0 * PI), theta / PI);
+
+        // dp/du points along the parallel, degenerates at the poles
+        let tangent = Vec3::new(normal.z, 0.0, -normal.x);
+        let tangent = if tangent.length_squared() > 1e-12 {
+            tangent.normalized()
+        } else {
+            Vec3::Z
+        };
+        (uv, tangent)
+    }
+}
+
+/// Parallelogram spanned by the edges `u` and `v` from the corner `origin`.
+#[derive(Clone, Debug)]
+pub struct Quad {
+    origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    /// Plane normal scaled to compute barycentric coordinates.
+    w: Vec3,
+    bbox: Aabb,
+    pub material: Arc<dyn Bsdf + Send + Sync>,
+}
+
+impl Quad {
+    /// Minimal thickness of the bounding box of axis aligned quads.
+    const BBOX_PADDING: f32 = 1e-4;
+
+    pub fn new(origin: Vec3, u: Vec3, v: Vec3, material: Arc<dyn Bsdf + Send + Sync>) -> Self {
+        let n = u.cross(&v);
+        let normal = n.normalized();
+        let w = n / n.length_squared();
+
+        let bbox = Aabb::from_corners(origin, origin + u + v)
+            .enclose(Aabb::from_corners(origin + u, origin + v));
+        let pad = |axis: Interval| {
+            if axis.length() < Self::BBOX_PADDING {
+                axis.expand(Self::BBOX_PADDING)
+            } else {
+                axis
+            }
+        };
+        let bbox = Aabb::new(pad(bbox.x_axis), pad(bbox.y_axis), pad(bbox.z_axis));
+
+        Self {
+            origin,
+            u,
+            v,
+            normal,
+            w,
+            bbox,
+            material,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(&ray.direction());
+        // Ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = self.normal.dot(&(self.origin - ray.origin())) / denom;
+        if !ray.clip_range(t_range).contains(t) {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let planar = point - self.origin;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let is_front_face = denom < 0.0;
+        let normal = if is_front_face {
+            self.normal
+        } else {
+            -self.normal
+        };
+
+        let hit = HitRecord {
             point,
             normal,
+            geometric_normal: normal,
+            tangent: self.u.normalized(),
+            uv: Vec2::new(alpha, beta),
             t,
+            time: ray.time(),
             is_front_face,
-            material: self.material,
-        })
+            material: self.material.as_ref(),
+            light: None,
+        };
+        hit.is_opaque(ray).then_some(hit)
     }
 
     fn bounding_box(&self) -> Aabb {
-        Aabb::from_center(self.center, Vec3::splat(self.radius))
+        self.bbox
     }
 }
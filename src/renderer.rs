@@ -1,74 +1,325 @@
+mod handle;
+mod progressive;
+mod tile;
+
+pub use handle::RenderHandle;
+pub use progressive::{Pass, StopConditions};
+pub use tile::*;
+
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 
 use crate::camera::Camera;
 use crate::color::Color;
-use crate::image::{Image, RenderTarget};
+use crate::image::{Film, Image, PixelBounds, RenderTarget};
 use crate::primitives::Ray;
+use crate::sampler::Sampler;
+use crate::scene;
 
 pub trait Renderer {
     fn render<F>(&self, camera: &Camera, image: &mut Image, ray_color: F)
     where
-        F: Fn(Ray) -> Color + Sync;
+        F: Fn(Ray, &mut dyn Sampler) -> Color + Sync,
+    {
+        self.render_with_handle(camera, image, ray_color, &RenderHandle::new());
+    }
+
+    /// Renders the image reporting the progress to the `handle`,
+    /// returns early leaving the remaining tiles black if it is cancelled.
+    fn render_with_handle<F>(
+        &self,
+        camera: &Camera,
+        image: &mut Image,
+        ray_color: F,
+        handle: &RenderHandle,
+    ) where
+        F: Fn(Ray, &mut dyn Sampler) -> Color + Sync;
+
+    /// Renders one sample per pixel per pass into a float accumulation buffer
+    /// until one of the `stop` conditions is met or the `handle` is cancelled, for interactive previews.
+    ///
+    /// After every pass the current image is written to the `target` and passed to `on_pass`.
+    /// Returns the state after the last finished pass, a cancelled pass is discarded.
+    fn render_progressive<T, F, C>(
+        &self,
+        camera: &Camera,
+        target: &mut T,
+        ray_color: F,
+        stop: StopConditions,
+        handle: &RenderHandle,
+        on_pass: C,
+    ) -> Pass
+    where
+        T: RenderTarget,
+        F: Fn(Ray, &mut dyn Sampler) -> Color + Sync,
+        C: FnMut(&Pass, &T);
 }
 
-#[derive(Default)]
-pub struct StRenderer;
+/// Renders the image tile by tile on the calling thread.
+pub struct StRenderer {
+    /// The width and height of the tiles in pixels.
+    ///
+    /// The image is identical to the one of [`MtRenderer`] with the same tile size.
+    pub tile_size: u32,
+}
+
+impl Default for StRenderer {
+    fn default() -> Self {
+        Self {
+            tile_size: DEFAULT_TILE_SIZE,
+        }
+    }
+}
 
+/// Renders the image on several threads, which pull small tiles from a shared queue,
+/// so all of them stay busy until the end regardless of where the scene is expensive.
 pub struct MtRenderer {
     n_workers: usize,
+    /// The width and height of the tiles in pixels, smaller tiles balance the load better.
+    ///
+    /// The image is identical to the one of [`StRenderer`] with the same tile size.
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
 }
 
 impl MtRenderer {
     pub fn new(n_workers: usize) -> Self {
-        Self { n_workers }
+        Self {
+            n_workers,
+            tile_size: DEFAULT_TILE_SIZE,
+            tile_order: TileOrder::default(),
+        }
     }
 }
 
 impl Default for MtRenderer {
     fn default() -> Self {
         let n_workers = std::thread::available_parallelism().map_or(1, |n| n.get());
-        Self { n_workers }
+        Self::new(n_workers)
+    }
+}
+
+impl StRenderer {
+    /// Renders the `samples` of all tiles, returns their films row by row.
+    fn render_tiles<F>(
+        &self,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+        samples: Range<u32>,
+        ray_color: &F,
+        handle: &RenderHandle,
+    ) -> Vec<Film>
+    where
+        F: Fn(Ray, &mut dyn Sampler) -> Color,
+    {
+        let (tiles, _, _) = tiles(width, height, self.tile_size);
+        tiles
+            .iter()
+            .take_while(|_| !handle.is_cancelled())
+            .map(|tile| {
+                render_tile(
+                    camera,
+                    width,
+                    height,
+                    tile,
+                    samples.clone(),
+                    ray_color,
+                    handle,
+                )
+            })
+            .collect()
     }
 }
 
 impl Renderer for StRenderer {
-    fn render<F>(&self, camera: &Camera, image: &mut Image, ray_color: F)
+    fn render_with_handle<F>(
+        &self,
+        camera: &Camera,
+        image: &mut Image,
+        ray_color: F,
+        handle: &RenderHandle,
+    ) where
+        F: Fn(Ray, &mut dyn Sampler) -> Color + Sync,
+    {
+        let width = image.get_width();
+        let height = image.get_height();
+        handle.add_work(width as u64 * height as u64);
+        let samples = 0..camera.samples_per_pixel();
+        let films = self.render_tiles(camera, width, height, samples, &ray_color, handle);
+        develop(camera, &films, image);
+    }
+
+    fn render_progressive<T, F, C>(
+        &self,
+        camera: &Camera,
+        target: &mut T,
+        ray_color: F,
+        stop: StopConditions,
+        handle: &RenderHandle,
+        on_pass: C,
+    ) -> Pass
     where
-        F: Fn(Ray) -> Color + Sync,
+        T: RenderTarget,
+        F: Fn(Ray, &mut dyn Sampler) -> Color + Sync,
+        C: FnMut(&Pass, &T),
     {
-        camera.render_to(image, ray_color);
+        let width = target.get_width();
+        let height = target.get_height();
+        let render_pass =
+            |samples| self.render_tiles(camera, width, height, samples, &ray_color, handle);
+        progressive::render_progressive(camera, target, stop, handle, render_pass, on_pass)
     }
 }
 
-impl Renderer for MtRenderer {
-    fn render<F>(&self, camera: &Camera, image: &mut Image, ray_color: F)
+impl MtRenderer {
+    /// Renders the `samples` of all tiles on the workers, returns their films row by row.
+    fn render_tiles<F>(
+        &self,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+        samples: Range<u32>,
+        ray_color: &F,
+        handle: &RenderHandle,
+    ) -> Vec<Film>
     where
-        F: Fn(Ray) -> Color + Sync,
+        F: Fn(Ray, &mut dyn Sampler) -> Color + Sync,
     {
-        let ray_color_ref = &ray_color;
+        let (tiles, columns, rows) = tiles(width, height, self.tile_size);
+        let schedule = self.tile_order.schedule(columns, rows);
+        let films = Mutex::new((0..tiles.len()).map(|_| None).collect::<Vec<_>>());
+        let next_tile = AtomicUsize::new(0);
+
         std::thread::scope(|s| {
-            for mut sub_image in image.split_n(self.n_workers as u32) {
-                s.spawn(move || {
+            for _ in 0..self.n_workers {
+                s.spawn(|| {
                     let thread_id = std::thread::current().id();
-                    let y_offset = sub_image.get_y_offset();
-                    log::debug!(
-                        "thread {:?} runs {}..{}",
-                        thread_id,
-                        y_offset,
-                        y_offset + sub_image.get_height()
-                    );
-
                     let timer = Instant::now();
-                    camera.render_to(&mut sub_image, ray_color_ref);
+                    let mut n_tiles = 0;
+
+                    // Workers take the next tile when they finish, so none of them stays idle
+                    while let Some(&index) = schedule.get(next_tile.fetch_add(1, Ordering::Relaxed))
+                    {
+                        if handle.is_cancelled() {
+                            break;
+                        }
+
+                        let tile = &tiles[index];
+                        log::trace!("thread {:?} runs tile {:?}x{:?}", thread_id, tile.x, tile.y);
+                        let film = render_tile(
+                            camera,
+                            width,
+                            height,
+                            tile,
+                            samples.clone(),
+                            ray_color,
+                            handle,
+                        );
+                        films.lock().unwrap()[index] = Some(film);
+                        n_tiles += 1;
+                    }
 
                     let render_time = timer.elapsed();
                     log::debug!(
-                        "thread {:?} finished in {}s",
+                        "thread {:?} finished {} tiles in {}s",
                         thread_id,
+                        n_tiles,
                         render_time.as_secs_f64()
                     );
                 });
             }
         });
+
+        films
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            // Tiles skipped after the cancellation stay black
+            .flatten()
+            .collect()
+    }
+}
+
+impl Renderer for MtRenderer {
+    fn render_with_handle<F>(
+        &self,
+        camera: &Camera,
+        image: &mut Image,
+        ray_color: F,
+        handle: &RenderHandle,
+    ) where
+        F: Fn(Ray, &mut dyn Sampler) -> Color + Sync,
+    {
+        let width = image.get_width();
+        let height = image.get_height();
+        handle.add_work(width as u64 * height as u64);
+        let samples = 0..camera.samples_per_pixel();
+        let films = self.render_tiles(camera, width, height, samples, &ray_color, handle);
+        develop(camera, &films, image);
+    }
+
+    fn render_progressive<T, F, C>(
+        &self,
+        camera: &Camera,
+        target: &mut T,
+        ray_color: F,
+        stop: StopConditions,
+        handle: &RenderHandle,
+        on_pass: C,
+    ) -> Pass
+    where
+        T: RenderTarget,
+        F: Fn(Ray, &mut dyn Sampler) -> Color + Sync,
+        C: FnMut(&Pass, &T),
+    {
+        let width = target.get_width();
+        let height = target.get_height();
+        let render_pass =
+            |samples| self.render_tiles(camera, width, height, samples, &ray_color, handle);
+        progressive::render_progressive(camera, target, stop, handle, render_pass, on_pass)
+    }
+}
+
+/// Renders the `samples` of the `tile` into a film extended by the filter margin
+/// and reports it to the `handle`.
+fn render_tile<F>(
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    tile: &PixelBounds,
+    samples: Range<u32>,
+    ray_color: &F,
+    handle: &RenderHandle,
+) -> Film
+where
+    F: Fn(Ray, &mut dyn Sampler) -> Color,
+{
+    // Samples near the tile edges are splatted into the neighboring tiles
+    let bounds = tile.expanded(camera.filter_margin(), width, height);
+    let mut film = Film::with_bounds(bounds);
+
+    // Tiles are rendered on a single thread, which counts its rays
+    let rays_traced = scene::rays_traced();
+    camera.render_to(&mut film, tile, samples, ray_color);
+    handle.finish_tile(tile.area() as u64, scene::rays_traced() - rays_traced);
+    film
+}
+
+/// Merges the films of the tiles row by row, independent of the order they were rendered in,
+/// and writes the result or the sample count heatmap of adaptive sampling to the `image`.
+fn develop(camera: &Camera, films: &[Film], image: &mut Image) {
+    let mut film = Film::new(image.get_width(), image.get_height());
+    for tile in films {
+        film.merge(tile);
+    }
+
+    match camera.adaptive {
+        Some(adaptive) if adaptive.show_sample_counts => {
+            film.write_sample_heatmap(image, camera.samples_per_pixel(), adaptive.max_samples)
+        }
+        _ => film.write_to(image),
     }
 }
@@ -0,0 +1,94 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Shared state of a render, to observe its progress and cancel it from other threads.
+///
+/// Clones refer to the same render, the renderers check for cancellation between tiles,
+/// so a cancelled render returns after the tiles in progress with the rest of the image black.
+#[derive(Clone, Debug, Default)]
+pub struct RenderHandle {
+    state: Arc<RenderState>,
+}
+
+#[derive(Debug, Default)]
+struct RenderState {
+    start: OnceLock<Instant>,
+    /// Pixels to render, counted once per pass.
+    total_pixels: AtomicU64,
+    rendered_pixels: AtomicU64,
+    rays_traced: AtomicU64,
+    is_cancelled: AtomicBool,
+}
+
+impl RenderHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the render at the next tile.
+    pub fn cancel(&self) {
+        self.state.is_cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.is_cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Percentage of the rendered pixels in `[0.0, 100.0]`.
+    ///
+    /// Progressive renders without a pass limit count the passes started so far.
+    pub fn progress(&self) -> f32 {
+        let total = self.state.total_pixels.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        let rendered = self.state.rendered_pixels.load(Ordering::Relaxed);
+        (rendered as f64 / total as f64 * 100.0).min(100.0) as f32
+    }
+
+    /// Time since the render started.
+    pub fn elapsed(&self) -> Duration {
+        self.state
+            .start
+            .get()
+            .map_or(Duration::ZERO, |start| start.elapsed())
+    }
+
+    /// Remaining time extrapolated from the speed so far, `None` until the first tile is finished.
+    pub fn eta(&self) -> Option<Duration> {
+        let total = self.state.total_pixels.load(Ordering::Relaxed);
+        let rendered = self.state.rendered_pixels.load(Ordering::Relaxed);
+        if rendered == 0 {
+            return None;
+        }
+        let remaining = total.saturating_sub(rendered);
+        Some(self.elapsed().mul_f64(remaining as f64 / rendered as f64))
+    }
+
+    /// The number of rays traced through the [`crate::scene::Scene`] so far, finished tiles only.
+    pub fn rays_traced(&self) -> u64 {
+        self.state.rays_traced.load(Ordering::Relaxed)
+    }
+
+    /// Adds `pixels` to the work of the render and starts its timer on the first call.
+    pub(crate) fn add_work(&self, pixels: u64) {
+        self.state.start.get_or_init(Instant::now);
+        self.state.total_pixels.fetch_add(pixels, Ordering::Relaxed);
+    }
+
+    /// Limits the work of the render to the pixels rendered so far, when it stops before the planned work.
+    pub(crate) fn finish(&self) {
+        let rendered = self.state.rendered_pixels.load(Ordering::Relaxed);
+        self.state.total_pixels.store(rendered, Ordering::Relaxed);
+    }
+
+    pub(crate) fn finish_tile(&self, pixels: u64, rays_traced: u64) {
+        self.state
+            .rendered_pixels
+            .fetch_add(pixels, Ordering::Relaxed);
+        self.state
+            .rays_traced
+            .fetch_add(rays_traced, Ordering::Relaxed);
+    }
+}
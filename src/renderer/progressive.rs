@@ -0,0 +1,167 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::image::{Film, RenderTarget};
+use crate::renderer::RenderHandle;
+
+/// Conditions ending a progressive render, it stops at the first one met.
+///
+/// Without any condition the render stops after the samples per pixel of the camera.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StopConditions {
+    /// The maximum number of passes, each adds one sample per pixel.
+    pub max_passes: Option<u32>,
+    /// Checked after every pass, so the render takes up to a pass longer.
+    pub time_budget: Option<Duration>,
+    /// Stops when the estimated noise (see [`Pass::noise`]) falls below this value,
+    /// checked after every even pass.
+    pub target_noise: Option<f32>,
+}
+
+impl StopConditions {
+    pub fn passes(max_passes: u32) -> Self {
+        Self {
+            max_passes: Some(max_passes),
+            ..Default::default()
+        }
+    }
+
+    pub fn time_budget(time_budget: Duration) -> Self {
+        Self {
+            time_budget: Some(time_budget),
+            ..Default::default()
+        }
+    }
+
+    pub fn target_noise(target_noise: f32) -> Self {
+        Self {
+            target_noise: Some(target_noise),
+            ..Default::default()
+        }
+    }
+
+    fn is_met(&self, pass: &Pass) -> bool {
+        self.max_passes.is_some_and(|max| pass.count >= max)
+            || self
+                .time_budget
+                .is_some_and(|budget| pass.elapsed >= budget)
+            || self
+                .target_noise
+                .zip(pass.noise)
+                .is_some_and(|(target, noise)| noise <= target)
+    }
+}
+
+/// State of a progressive render after a pass.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pass {
+    /// The number of finished passes, equal to the samples per pixel.
+    pub count: u32,
+    /// Time since the start of the render.
+    pub elapsed: Duration,
+    /// Root mean square of the relative error of the pixel luminance,
+    /// estimated after every even pass when the half image holds exactly half of the samples.
+    pub noise: Option<f32>,
+}
+
+/// Runs passes of `render_pass` until one of the `stop` conditions is met,
+/// `render_pass` renders the sample indices into the films of its tiles.
+pub(super) fn render_progressive<T, R, C>(
+    camera: &Camera,
+    target: &mut T,
+    stop: StopConditions,
+    handle: &RenderHandle,
+    mut render_pass: R,
+    mut on_pass: C,
+) -> Pass
+where
+    T: RenderTarget,
+    R: FnMut(Range<u32>) -> Vec<Film>,
+    C: FnMut(&Pass, &T),
+{
+    let stop = if stop == StopConditions::default() {
+        StopConditions::passes(camera.samples_per_pixel())
+    } else {
+        stop
+    };
+
+    let width = target.get_width();
+    let height = target.get_height();
+    // Even passes are also summed separately, the difference of both images estimates the noise
+    let mut film = Film::new(width, height);
+    let mut half_film = Film::new(width, height);
+    let pixels = width as u64 * height as u64;
+    if let Some(max_passes) = stop.max_passes {
+        handle.add_work(pixels * max_passes as u64);
+    }
+    let timer = Instant::now();
+
+    let mut last_pass = Pass {
+        count: 0,
+        elapsed: Duration::ZERO,
+        noise: None,
+    };
+    loop {
+        let index = last_pass.count;
+        if stop.max_passes.is_none() {
+            handle.add_work(pixels);
+        }
+
+        let mut pass_film = Film::new(width, height);
+        for tile in render_pass(index..index + 1) {
+            pass_film.merge(&tile);
+        }
+        // Some tiles of the pass are missing
+        if handle.is_cancelled() {
+            return last_pass;
+        }
+        film.merge(&pass_film);
+        if index.is_multiple_of(2) {
+            half_film.merge(&pass_film);
+        }
+
+        let count = index + 1;
+        let pass = Pass {
+            count,
+            elapsed: timer.elapsed(),
+            noise: count
+                .is_multiple_of(2)
+                .then(|| estimate_noise(&film, &half_film)),
+        };
+        log::debug!(
+            "pass {} finished in {}s, noise {:?}",
+            pass.count,
+            pass.elapsed.as_secs_f64(),
+            pass.noise
+        );
+
+        film.write_to(target);
+        on_pass(&pass, target);
+        if stop.is_met(&pass) {
+            // Time or noise may stop the render before the pass limit
+            handle.finish();
+            return pass;
+        }
+        last_pass = pass;
+    }
+}
+
+/// Relative noise of the `film` estimated from the `half_film` of half of its samples.
+///
+/// The difference of the means of all samples and their half has the same variance
+/// as the mean of all samples, so it measures the error of the image.
+fn estimate_noise(film: &Film, half_film: &Film) -> f32 {
+    // Keeps dark pixels from dominating the relative error
+    const BLACK_LEVEL: f32 = 0.01;
+
+    let bounds = film.bounds();
+    let mut sum = 0.0;
+    for (x, y) in bounds.pixels() {
+        let luminance = film.pixel(x, y).luminance();
+        let half_luminance = half_film.pixel(x, y).luminance();
+        let error = (luminance - half_luminance) / (luminance.abs() + BLACK_LEVEL);
+        sum += (error * error) as f64;
+    }
+    (sum / bounds.area().max(1) as f64).sqrt() as f32
+}
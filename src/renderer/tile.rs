@@ -0,0 +1,97 @@
+use crate::image::PixelBounds;
+
+/// The width and height of the tiles in pixels used by the renderers by default.
+///
+/// Samples splatted across the tile edges are summed in the order of the tiles,
+/// so images rendered with the same tile size are identical for any scheduling.
+pub const DEFAULT_TILE_SIZE: u32 = 32;
+
+/// Order in which the tiles of the image are handed out to the workers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TileOrder {
+    /// Row by row from the top left tile.
+    Scanline,
+    /// Along the Hilbert curve, consecutive tiles are neighbors and see similar parts of the scene.
+    #[default]
+    Hilbert,
+    /// Ring by ring outwards from the image center, where the subject usually is.
+    Spiral,
+}
+
+impl TileOrder {
+    /// Returns the indices of the tiles of a grid with `columns` and `rows`
+    /// in the order they should be rendered, tiles are indexed row by row.
+    pub(crate) fn schedule(&self, columns: u32, rows: u32) -> Vec<usize> {
+        let index = |(x, y): (u32, u32)| y as usize * columns as usize + x as usize;
+        match self {
+            Self::Scanline => (0..columns as usize * rows as usize).collect(),
+            Self::Hilbert => {
+                let n = columns.max(rows).next_power_of_two();
+                (0..n * n)
+                    .map(|d| hilbert_point(n, d))
+                    .filter(|&(x, y)| x < columns && y < rows)
+                    .map(index)
+                    .collect()
+            }
+            Self::Spiral => {
+                let center_x = columns as f32 / 2.0;
+                let center_y = rows as f32 / 2.0;
+                let mut tiles = (0..rows)
+                    .flat_map(|y| (0..columns).map(move |x| (x, y)))
+                    .map(|(x, y)| {
+                        let dx = x as f32 + 0.5 - center_x;
+                        let dy = y as f32 + 0.5 - center_y;
+                        let ring = dx.abs().max(dy.abs()).round() as u32;
+                        (ring, dy.atan2(dx), index((x, y)))
+                    })
+                    .collect::<Vec<_>>();
+                tiles.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+                tiles.into_iter().map(|(_, _, index)| index).collect()
+            }
+        }
+    }
+}
+
+/// Splits the image into tiles of `tile_size` pixels row by row, the last column and row may be smaller.
+///
+/// Returns the tiles with the number of columns and rows of the grid.
+pub(crate) fn tiles(width: u32, height: u32, tile_size: u32) -> (Vec<PixelBounds>, u32, u32) {
+    let tile_size = tile_size.max(1);
+    let columns = width.div_ceil(tile_size);
+    let rows = height.div_ceil(tile_size);
+
+    let mut tiles = Vec::with_capacity(columns as usize * rows as usize);
+    for y in (0..height).step_by(tile_size as usize) {
+        for x in (0..width).step_by(tile_size as usize) {
+            tiles.push(PixelBounds {
+                x: x..(x + tile_size).min(width),
+                y: y..(y + tile_size).min(height),
+            });
+        }
+    }
+    (tiles, columns, rows)
+}
+
+/// Point at the distance `d` along the Hilbert curve filling a `n` by `n` grid.
+fn hilbert_point(n: u32, d: u32) -> (u32, u32) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        // Rotates the quadrant, so the curve of the sub-grid connects to its neighbors
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
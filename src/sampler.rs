@@ -0,0 +1,148 @@
+mod blue_noise;
+mod halton;
+mod independent;
+mod sobol;
+mod stratified;
+
+pub use blue_noise::*;
+pub use halton::*;
+pub use independent::*;
+pub use sobol::*;
+pub use stratified::*;
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use crate::math::{Vec2, Vec3};
+
+/// The largest `f32` below `1.0`, keeps sample values in `[0.0, 1.0)`.
+pub const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/// Source of sample values for Monte Carlo integration.
+///
+/// Values are requested by dimension: every sample of a pixel consumes the dimensions
+/// in the same order, so samplers can distribute each dimension well over the samples of the pixel.
+pub trait Sampler {
+    /// Starts the sample `index` of the image `pixel`, restarting at the first dimension.
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32);
+
+    /// Returns the value of the next dimension in `[0.0, 1.0)`.
+    fn get_1d(&mut self) -> f32;
+
+    /// Returns the values of the next two dimensions in `[0.0, 1.0)^2`.
+    fn get_2d(&mut self) -> Vec2;
+
+    /// Returns the position of the sample within the pixel, requested first by the camera.
+    fn get_pixel_2d(&mut self) -> Vec2 {
+        self.get_2d()
+    }
+}
+
+/// Strategy of generating samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sampling {
+    /// Uniform random values without any correlation.
+    Independent,
+    /// Jittered strata of each dimension, shuffled between dimensions.
+    Stratified,
+    /// Halton sequence with Owen-scrambled digits.
+    Halton,
+    /// Owen-scrambled Sobol points, padded by shuffling the sample order of each dimension.
+    #[default]
+    Sobol,
+    /// Sobol points rotated by blue noise, the error of neighboring pixels is negatively correlated.
+    BlueNoise,
+}
+
+impl Sampling {
+    /// Creates a sampler for `samples_per_pixel` samples, different `seed`s give independent images.
+    pub fn create(&self, samples_per_pixel: u32, seed: u64) -> Box<dyn Sampler + Send> {
+        match self {
+            Self::Independent => Box::new(IndependentSampler::new(seed)),
+            Self::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel, seed)),
+            Self::Halton => Box::new(HaltonSampler::new(seed)),
+            Self::Sobol => Box::new(SobolSampler::new(samples_per_pixel, seed)),
+            Self::BlueNoise => Box::new(BlueNoiseSampler::new(samples_per_pixel, seed)),
+        }
+    }
+}
+
+/// Maps `u` to the unit disk by the concentric mapping, which keeps the stratification of `u`.
+pub fn sample_uniform_disk(u: Vec2) -> Vec2 {
+    let offset = u * 2.0 - Vec2::ONE;
+    if offset.x == 0.0 && offset.y == 0.0 {
+        return Vec2::ZERO;
+    }
+
+    let (r, theta) = if offset.x.abs() > offset.y.abs() {
+        (offset.x, FRAC_PI_4 * (offset.y / offset.x))
+    } else {
+        (offset.y, FRAC_PI_2 - FRAC_PI_4 * (offset.x / offset.y))
+    };
+    Vec2::new(r * theta.cos(), r * theta.sin())
+}
+
+/// Maps `u` to a uniformly distributed unit vector.
+pub fn sample_uniform_sphere(u: Vec2) -> Vec3 {
+    let z = 1.0 - 2.0 * u.x;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.y;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Finalizer scrambling all bits of `v`.
+#[inline]
+pub(crate) fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    v
+}
+
+/// Combines the `values` into a single well distributed hash.
+#[inline]
+pub(crate) fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e3779b97f4a7c15, |hash, &value| {
+        mix_bits(hash ^ mix_bits(value.wrapping_add(0x9e3779b97f4a7c15)))
+    })
+}
+
+/// Returns the element `index` of a random permutation of `0..len` chosen by `seed`,
+/// without storing the permutation (Kensler, "Correlated Multi-Jittered Sampling").
+pub(crate) fn permutation_element(index: u32, len: u32, seed: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    let p = seed;
+    let mut i = index;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        // Cycle walking until the element falls into the range
+        if i < len {
+            break;
+        }
+    }
+    ((i as u64 + p as u64) % len as u64) as u32
+}
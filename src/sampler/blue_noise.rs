@@ -0,0 +1,202 @@
+use std::sync::OnceLock;
+
+use crate::math::Vec2;
+use crate::sampler::sobol::{sobol_dimension_1, sobol_sample};
+use crate::sampler::{ONE_MINUS_EPSILON, Sampler, hash, mix_bits, permutation_element};
+
+/// The width and height of the tiled blue noise texture.
+const TILE_SIZE: usize = 64;
+const TILE_PIXELS: usize = TILE_SIZE * TILE_SIZE;
+
+/// Padded Sobol points rotated in each pixel by a blue noise texture (Cranley-Patterson rotation).
+///
+/// All pixels share the same points, so the first samples of neighboring pixels differ
+/// as much as possible and the remaining error looks like fine grain instead of blotches.
+#[derive(Clone, Debug)]
+pub struct BlueNoiseSampler {
+    samples_per_pixel: u32,
+    seed: u64,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u32,
+}
+
+impl BlueNoiseSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1),
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Returns the shuffled sample index and the seed of the next dimension, same for all pixels.
+    fn next_dimension(&mut self) -> (u32, u64) {
+        let seed = hash(&[self.dimension as u64, self.seed]);
+        self.dimension += 1;
+
+        let block = self.index - self.index % self.samples_per_pixel;
+        let index = block
+            + permutation_element(
+                self.index % self.samples_per_pixel,
+                self.samples_per_pixel,
+                seed as u32,
+            );
+        (index, seed)
+    }
+
+    /// Blue noise value of the pixel, the texture is shifted differently for every dimension.
+    fn offset(&self, seed: u64) -> f32 {
+        let x = (self.pixel.0 as usize + seed as usize) % TILE_SIZE;
+        let y = (self.pixel.1 as usize + (seed >> 32) as usize) % TILE_SIZE;
+        let rank = blue_noise_ranks()[y * TILE_SIZE + x];
+        (rank as f32 + 0.5) / TILE_PIXELS as f32
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let (index, seed) = self.next_dimension();
+        let value = sobol_sample(index.reverse_bits(), (seed >> 32) as u32);
+        rotate(value, self.offset(mix_bits(seed)))
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let (index, seed) = self.next_dimension();
+        let seed_y = mix_bits(seed);
+        let x = sobol_sample(index.reverse_bits(), (seed >> 32) as u32);
+        let y = sobol_sample(sobol_dimension_1(index), (seed_y >> 32) as u32);
+        Vec2::new(
+            rotate(x, self.offset(seed_y)),
+            rotate(y, self.offset(mix_bits(seed_y))),
+        )
+    }
+}
+
+/// Shifts the `value` by the `offset` with wrap around.
+#[inline]
+fn rotate(value: f32, offset: f32) -> f32 {
+    (value + offset).fract().min(ONE_MINUS_EPSILON)
+}
+
+/// Ranks of the pixels of a tileable blue noise texture, each rank appears once.
+fn blue_noise_ranks() -> &'static [u16] {
+    static RANKS: OnceLock<Vec<u16>> = OnceLock::new();
+    RANKS.get_or_init(void_and_cluster)
+}
+
+/// Generates blue noise ranks by the void-and-cluster method (Ulichney, 1993).
+///
+/// Points are added into the largest voids one by one, the energy of a pixel
+/// is the sum of Gaussians centered at the points on the torus.
+fn void_and_cluster() -> Vec<u16> {
+    const SIGMA: f32 = 1.5;
+    const INITIAL_POINTS: usize = TILE_PIXELS / 10;
+
+    let mut kernel = vec![0.0; TILE_PIXELS];
+    for y in 0..TILE_SIZE {
+        for x in 0..TILE_SIZE {
+            let dx = x.min(TILE_SIZE - x) as f32;
+            let dy = y.min(TILE_SIZE - y) as f32;
+            kernel[y * TILE_SIZE + x] = f32::exp(-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA));
+        }
+    }
+
+    let mut field = EnergyField {
+        kernel,
+        energy: vec![0.0; TILE_PIXELS],
+        points: vec![false; TILE_PIXELS],
+    };
+
+    // Random initial points are spread evenly by moving the tightest cluster into the largest void
+    let mut rng = fastrand::Rng::with_seed(0x5eed);
+    while field.points.iter().filter(|&&point| point).count() < INITIAL_POINTS {
+        let index = rng.usize(..TILE_PIXELS);
+        if !field.points[index] {
+            field.toggle(index);
+        }
+    }
+    for _ in 0..TILE_PIXELS {
+        let cluster = field.tightest_cluster();
+        field.toggle(cluster);
+        let void = field.largest_void();
+        if void == cluster {
+            field.toggle(cluster);
+            break;
+        }
+        field.toggle(void);
+    }
+    let initial = field.clone();
+
+    let mut ranks = vec![0; TILE_PIXELS];
+    // Points of the initial pattern get ranks from the tightest cluster down
+    for rank in (0..INITIAL_POINTS).rev() {
+        let cluster = field.tightest_cluster();
+        field.toggle(cluster);
+        ranks[cluster] = rank as u16;
+    }
+
+    // The rest fill the largest voids
+    let mut field = initial;
+    for rank in INITIAL_POINTS..TILE_PIXELS {
+        let void = field.largest_void();
+        field.toggle(void);
+        ranks[void] = rank as u16;
+    }
+
+    ranks
+}
+
+#[derive(Clone)]
+struct EnergyField {
+    kernel: Vec<f32>,
+    energy: Vec<f32>,
+    points: Vec<bool>,
+}
+
+impl EnergyField {
+    /// Adds or removes the point at `index` and updates the energy of all pixels.
+    fn toggle(&mut self, index: usize) {
+        self.points[index] = !self.points[index];
+        let sign = if self.points[index] { 1.0 } else { -1.0 };
+
+        let (px, py) = (index % TILE_SIZE, index / TILE_SIZE);
+        for y in 0..TILE_SIZE {
+            let dy = (y + TILE_SIZE - py) % TILE_SIZE;
+            for x in 0..TILE_SIZE {
+                let dx = (x + TILE_SIZE - px) % TILE_SIZE;
+                self.energy[y * TILE_SIZE + x] += sign * self.kernel[dy * TILE_SIZE + dx];
+            }
+        }
+    }
+
+    fn tightest_cluster(&self) -> usize {
+        self.extreme(true, |a, b| a > b)
+    }
+
+    fn largest_void(&self) -> usize {
+        self.extreme(false, |a, b| a < b)
+    }
+
+    /// Index of the pixel with or without a point with the extreme energy.
+    fn extreme(&self, point: bool, is_better: impl Fn(f32, f32) -> bool) -> usize {
+        let mut best = None;
+        for (index, &energy) in self.energy.iter().enumerate() {
+            if self.points[index] != point {
+                continue;
+            }
+            if best.is_none_or(|(_, best_energy)| is_better(energy, best_energy)) {
+                best = Some((index, energy));
+            }
+        }
+        best.map_or(0, |(index, _)| index)
+    }
+}
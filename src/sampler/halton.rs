@@ -0,0 +1,117 @@
+use crate::math::Vec2;
+use crate::sampler::{ONE_MINUS_EPSILON, Sampler, hash, mix_bits};
+
+/// Number of dimensions with their own prime base, the rest are uniform random.
+const PRIME_TABLE_SIZE: usize = 256;
+
+const PRIMES: [u32; PRIME_TABLE_SIZE] = primes();
+
+/// Halton sequence, the dimension `i` is the radical inverse of the sample index in the `i`-th prime base.
+///
+/// Digits are Owen-scrambled with a different seed for every pixel and dimension,
+/// which decorrelates pixels while keeping the stratification of the sequence.
+#[derive(Clone, Debug)]
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u32,
+    rng: fastrand::Rng,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+
+    fn sample_dimension(&mut self) -> f32 {
+        let dimension = self.dimension as usize;
+        self.dimension += 1;
+        let Some(&base) = PRIMES.get(dimension) else {
+            return self.rng.f32();
+        };
+
+        let seed = hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            dimension as u64,
+            self.seed,
+        ]);
+        owen_scrambled_radical_inverse(base, self.index as u64, seed as u32)
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+        self.rng.seed(hash(&[
+            pixel.0 as u64,
+            pixel.1 as u64,
+            index as u64,
+            self.seed,
+        ]));
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        self.sample_dimension()
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let x = self.sample_dimension();
+        let y = self.sample_dimension();
+        Vec2::new(x, y)
+    }
+}
+
+/// Mirrors the digits of `a` in the `base` around the radix point,
+/// each digit is rotated by a random amount depending on the digits before it.
+fn owen_scrambled_radical_inverse(base: u32, mut a: u64, seed: u32) -> f32 {
+    let base_u64 = base as u64;
+    let inv_base = 1.0 / base as f32;
+    let mut inv_base_m = 1.0;
+    let mut reversed_digits = 0u64;
+
+    // Stops when the remaining digits are below the precision of `f32`
+    while 1.0 - (base - 1) as f32 * inv_base_m < 1.0 {
+        let next = a / base_u64;
+        let digit = a - next * base_u64;
+        let digit_shift = mix_bits(seed as u64 ^ reversed_digits) % base_u64;
+        let digit = (digit + digit_shift) % base_u64;
+        reversed_digits = reversed_digits * base_u64 + digit;
+        inv_base_m *= inv_base;
+        a = next;
+    }
+
+    (inv_base_m * reversed_digits as f32).min(ONE_MINUS_EPSILON)
+}
+
+const fn primes() -> [u32; PRIME_TABLE_SIZE] {
+    let mut primes = [0; PRIME_TABLE_SIZE];
+    let mut count = 0;
+    let mut n = 2;
+    while count < PRIME_TABLE_SIZE {
+        let mut is_prime = true;
+        let mut i = 0;
+        while i < count && primes[i] * primes[i] <= n {
+            if n % primes[i] == 0 {
+                is_prime = false;
+                break;
+            }
+            i += 1;
+        }
+        if is_prime {
+            primes[count] = n;
+            count += 1;
+        }
+        n += 1;
+    }
+    primes
+}
@@ -0,0 +1,33 @@
+use crate::math::Vec2;
+use crate::sampler::{Sampler, hash};
+
+/// Uniform random values, each sample of a pixel has its own deterministic random stream.
+#[derive(Clone, Debug)]
+pub struct IndependentSampler {
+    seed: u64,
+    rng: fastrand::Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        let seed = hash(&[pixel.0 as u64, pixel.1 as u64, index as u64, self.seed]);
+        self.rng.seed(seed);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        self.rng.f32()
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        Vec2::new(self.rng.f32(), self.rng.f32())
+    }
+}
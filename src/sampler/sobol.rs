@@ -0,0 +1,124 @@
+use crate::math::Vec2;
+use crate::sampler::{ONE_MINUS_EPSILON, Sampler, hash, mix_bits, permutation_element};
+
+/// Generator matrix of the second Sobol dimension, the first one reverses the bits of the index.
+const SOBOL_MATRIX_1: [u32; 32] = sobol_matrix_1();
+
+/// Owen-scrambled Sobol (0,2)-sequence padded to any number of dimensions.
+///
+/// Every 1D or 2D request uses the first Sobol dimensions with the order of the samples
+/// shuffled by its own permutation, which keeps the stratification of each request
+/// and decorrelates the requests. Works best with a power of two samples per pixel.
+#[derive(Clone, Debug)]
+pub struct SobolSampler {
+    samples_per_pixel: u32,
+    seed: u64,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1),
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Returns the shuffled sample index and the seed of the next request.
+    fn next_request(&mut self) -> (u32, u64) {
+        let seed = hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.dimension as u64,
+            self.seed,
+        ]);
+        self.dimension += 1;
+
+        // Samples beyond the count continue the sequence in blocks of the same size
+        let block = self.index - self.index % self.samples_per_pixel;
+        let index = block
+            + permutation_element(
+                self.index % self.samples_per_pixel,
+                self.samples_per_pixel,
+                seed as u32,
+            );
+        (index, seed)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let (index, seed) = self.next_request();
+        sobol_sample(index.reverse_bits(), (seed >> 32) as u32)
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let (index, seed) = self.next_request();
+        let seed_y = mix_bits(seed);
+        Vec2::new(
+            sobol_sample(index.reverse_bits(), (seed >> 32) as u32),
+            sobol_sample(sobol_dimension_1(index), (seed_y >> 32) as u32),
+        )
+    }
+}
+
+/// Converts the bits of a Sobol point to a value in `[0.0, 1.0)` after Owen scrambling.
+#[inline]
+pub(super) fn sobol_sample(bits: u32, seed: u32) -> f32 {
+    let bits = owen_scramble(bits, seed);
+    (bits as f32 * 2.0f32.powi(-32)).min(ONE_MINUS_EPSILON)
+}
+
+#[inline]
+pub(super) fn sobol_dimension_1(mut index: u32) -> u32 {
+    let mut bits = 0;
+    let mut column = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            bits ^= SOBOL_MATRIX_1[column];
+        }
+        index >>= 1;
+        column += 1;
+    }
+    bits
+}
+
+/// Randomly flips each bit depending on all more significant bits, which permutes
+/// the elementary intervals of the point set without breaking the stratification.
+///
+/// Uses the hash of Laine and Karras, where bits only affect the less significant ones
+/// after reversing the bits.
+#[inline]
+fn owen_scramble(v: u32, seed: u32) -> u32 {
+    let mut v = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+    v.reverse_bits()
+}
+
+/// Direction numbers of the primitive polynomial `x + 1`, `m_k = m_(k-1) ^ (m_(k-1) << 1)`.
+const fn sobol_matrix_1() -> [u32; 32] {
+    let mut matrix = [0; 32];
+    let mut m: u64 = 1;
+    let mut column = 0;
+    while column < 32 {
+        matrix[column] = (m << (31 - column)) as u32;
+        m ^= m << 1;
+        column += 1;
+    }
+    matrix
+}
@@ -0,0 +1,82 @@
+use crate::math::Vec2;
+use crate::sampler::{ONE_MINUS_EPSILON, Sampler, hash, permutation_element};
+
+/// Jittered stratified samples, each dimension is split into one stratum per sample.
+///
+/// Strata are assigned to samples by a random permutation of every dimension,
+/// so different dimensions are not correlated.
+#[derive(Clone, Debug)]
+pub struct StratifiedSampler {
+    samples_per_pixel: u32,
+    /// Strata along `x` and `y` of two dimensional samples.
+    strata_2d: (u32, u32),
+    seed: u64,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u32,
+    rng: fastrand::Rng,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        // Some strata stay empty when the number of samples is not a product of two close numbers
+        let x_strata = samples_per_pixel.isqrt();
+        let y_strata = samples_per_pixel.div_ceil(x_strata);
+        Self {
+            samples_per_pixel,
+            strata_2d: (x_strata, y_strata),
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+
+    /// Stratum of the current sample in the dimension among `strata`.
+    fn stratum(&mut self, strata: u32) -> u32 {
+        // Samples beyond the count start another round of strata
+        let round = self.index / self.samples_per_pixel;
+        let seed = hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.dimension as u64,
+            round as u64,
+            self.seed,
+        ]);
+        self.dimension += 1;
+        permutation_element(self.index % self.samples_per_pixel, strata, seed as u32)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+        self.rng.seed(hash(&[
+            pixel.0 as u64,
+            pixel.1 as u64,
+            index as u64,
+            self.seed,
+        ]));
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let stratum = self.stratum(self.samples_per_pixel);
+        let value = (stratum as f32 + self.rng.f32()) / self.samples_per_pixel as f32;
+        value.min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let (x_strata, y_strata) = self.strata_2d;
+        let stratum = self.stratum(x_strata * y_strata);
+        // Two dimensions share one permutation of the strata
+        self.dimension += 1;
+
+        let x = ((stratum % x_strata) as f32 + self.rng.f32()) / x_strata as f32;
+        let y = ((stratum / x_strata) as f32 + self.rng.f32()) / y_strata as f32;
+        Vec2::new(x.min(ONE_MINUS_EPSILON), y.min(ONE_MINUS_EPSILON))
+    }
+}
@@ -0,0 +1,130 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use crate::color::Color;
+use crate::light::{
+    BvhLightSampler, Light, LightSampler, LightSampling, PowerLightSampler, UniformLightSampler,
+};
+use crate::math::Interval;
+use crate::primitives::{HitRecord, Hittable, Ray};
+
+thread_local! {
+    /// Rays traced through any scene by the current thread, for progress reporting.
+    static RAYS_TRACED: Cell<u64> = const { Cell::new(0) };
+}
+
+/// The number of rays traced through any scene by the current thread.
+pub(crate) fn rays_traced() -> u64 {
+    RAYS_TRACED.get()
+}
+
+/// Geometry of the scene together with the lights illuminating it.
+pub struct Scene {
+    world: Arc<dyn Hittable + Send + Sync>,
+    lights: Vec<Arc<dyn Light + Send + Sync>>,
+    /// Indices of the lights at infinity.
+    infinite_lights: Vec<usize>,
+    /// Indices of the lights by the address of their data.
+    light_indices: HashMap<usize, usize>,
+    light_sampling: LightSampling,
+    /// Built on the first use, since it depends on all of the lights.
+    light_sampler: OnceLock<Box<dyn LightSampler + Send + Sync>>,
+    /// Radiance of rays escaping the scene.
+    pub background: Color,
+}
+
+impl Scene {
+    pub fn new(world: Arc<dyn Hittable + Send + Sync>) -> Self {
+        Self {
+            world,
+            lights: Vec::new(),
+            infinite_lights: Vec::new(),
+            light_indices: HashMap::new(),
+            light_sampling: LightSampling::default(),
+            light_sampler: OnceLock::new(),
+            background: Color::BLACK,
+        }
+    }
+
+    /// Adds a light sampled by next-event estimation.
+    ///
+    /// Lights which are also part of the world must be added as the same `Arc`.
+    pub fn add_light(&mut self, light: Arc<dyn Light + Send + Sync>) {
+        let index = self.lights.len();
+        if light.is_infinite() {
+            self.infinite_lights.push(index);
+        }
+        self.light_indices
+            .insert(light_address(light.as_ref()), index);
+        self.lights.push(light);
+        self.light_sampler = OnceLock::new();
+    }
+
+    pub fn set_light_sampling(&mut self, light_sampling: LightSampling) {
+        self.light_sampling = light_sampling;
+        self.light_sampler = OnceLock::new();
+    }
+
+    pub fn world(&self) -> &(dyn Hittable + Send + Sync) {
+        self.world.as_ref()
+    }
+
+    pub fn lights(&self) -> &[Arc<dyn Light + Send + Sync>] {
+        &self.lights
+    }
+
+    /// Lights at infinity with their indices, they are hit by rays escaping the scene.
+    pub fn infinite_lights(&self) -> impl Iterator<Item = (usize, &(dyn Light + Send + Sync))> {
+        self.infinite_lights
+            .iter()
+            .map(|&index| (index, self.lights[index].as_ref()))
+    }
+
+    /// Returns the index of the `light` in [`Scene::lights`].
+    pub fn light_index(&self, light: &dyn Light) -> Option<usize> {
+        self.light_indices.get(&light_address(light)).copied()
+    }
+
+    /// Sampler choosing the light for next-event estimation.
+    pub fn light_sampler(&self) -> &(dyn LightSampler + Send + Sync) {
+        self.light_sampler
+            .get_or_init(|| match self.light_sampling {
+                LightSampling::Uniform => Box::new(UniformLightSampler::new(&self.lights)),
+                LightSampling::Power => {
+                    Box::new(PowerLightSampler::new(&self.lights, self.radius()))
+                }
+                LightSampling::Bvh => Box::new(BvhLightSampler::new(&self.lights)),
+            })
+            .as_ref()
+    }
+
+    /// Radius of the sphere bounding the world.
+    pub fn radius(&self) -> f32 {
+        let radius = self.world.bounding_box().diagonal().length() * 0.5;
+        if radius.is_finite() && radius > 0.0 {
+            radius
+        } else {
+            1.0
+        }
+    }
+
+    /// Finds the closest intersection of the `ray` within `t_range`, limited by [`Ray::t_max`].
+    #[inline]
+    pub fn hit(&self, ray: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        RAYS_TRACED.set(RAYS_TRACED.get() + 1);
+        self.world.hit(ray, t_range)
+    }
+
+    /// Checks if anything blocks the `ray` within `t_range`, limited by [`Ray::t_max`].
+    #[inline]
+    pub fn is_occluded(&self, ray: &Ray, t_range: Interval) -> bool {
+        RAYS_TRACED.set(RAYS_TRACED.get() + 1);
+        self.world.hit(ray, t_range).is_some()
+    }
+}
+
+/// Identifies a light by the address of its data, shared by all `Arc`s and references to it.
+fn light_address(light: &dyn Light) -> usize {
+    light as *const dyn Light as *const () as usize
+}
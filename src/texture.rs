@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use crate::color::Color;
+use crate::image::{hdr, ppm};
+use crate::math::Vec2;
+
+/// Encoding of the color values stored in an image file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Values are gamma encoded with the sRGB transfer function (albedo, emission).
+    Srgb,
+    /// Values are stored as is (normals, heights, masks).
+    Linear,
+}
+
+/// Image texture with linear RGB texels.
+///
+/// Texture coordinates wrap around, `(0.0, 0.0)` is the bottom left corner.
+#[derive(Clone, Debug)]
+pub struct Texture {
+    width: u32,
+    height: u32,
+    texels: Vec<Color>,
+}
+
+impl Texture {
+    pub fn new(width: u32, height: u32, texels: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0, "Texture must not be empty");
+        assert_eq!(
+            texels.len(),
+            width as usize * height as usize,
+            "Size of texels is incorrect"
+        );
+        Self {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    /// Creates a single texel texture.
+    pub fn constant(color: Color) -> Self {
+        Self::new(1, 1, vec![color])
+    }
+
+    pub fn load_ppm<P: AsRef<Path>>(path: P, color_space: ColorSpace) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let (width, height, pixels) = ppm::read(&mut reader)?;
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Texture must not be empty",
+            ));
+        }
+
+        let decode = |value: u8| {
+            let value = value as f32 / 255.0;
+            match color_space {
+                ColorSpace::Srgb => srgb_to_linear(value),
+                ColorSpace::Linear => value,
+            }
+        };
+        let texels = pixels
+            .chunks_exact(3)
+            .map(|rgb| Color::new(decode(rgb[0]), decode(rgb[1]), decode(rgb[2])))
+            .collect();
+
+        Ok(Self::new(width, height, texels))
+    }
+
+    /// Loads a Radiance RGBE (`.hdr`) image with linear values.
+    pub fn load_hdr<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let (width, height, pixels) = hdr::read(&mut reader)?;
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Texture must not be empty",
+            ));
+        }
+
+        let texels = pixels
+            .chunks_exact(3)
+            .map(|rgb| Color::new(rgb[0], rgb[1], rgb[2]))
+            .collect();
+
+        Ok(Self::new(width, height, texels))
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the texel at `(x, y)`, where `y = 0` is the top row.
+    #[inline]
+    pub fn texel(&self, x: u32, y: u32) -> Color {
+        self.texels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Samples the texture with bilinear filtering, repeating it along both axes.
+    pub fn sample(&self, uv: Vec2) -> Color {
+        self.bilinear(uv, false)
+    }
+
+    /// Samples an equirectangular map with bilinear filtering, repeating it along `u` only,
+    /// so the rows at the poles are not blended together.
+    pub fn sample_spherical(&self, uv: Vec2) -> Color {
+        self.bilinear(uv, true)
+    }
+
+    fn bilinear(&self, uv: Vec2, clamp_v: bool) -> Color {
+        let x = uv.x * self.width as f32 - 0.5;
+        let y = (1.0 - uv.y) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        let wrap = |value: f32, size: u32| (value as i64).rem_euclid(size as i64) as u32;
+        let wrap_y = |value: f32| {
+            if clamp_v {
+                (value as i64).clamp(0, self.height as i64 - 1) as u32
+            } else {
+                wrap(value, self.height)
+            }
+        };
+        let (x1, y1) = (wrap(x0 + 1.0, self.width), wrap_y(y0 + 1.0));
+        let (x0, y0) = (wrap(x0, self.width), wrap_y(y0));
+
+        let top = self.texel(x0, y0) * (1.0 - tx) + self.texel(x1, y0) * tx;
+        let bottom = self.texel(x0, y1) * (1.0 - tx) + self.texel(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use spacer::camera::{Camera, CameraParams, Filter};
+use spacer::color::Color;
+use spacer::image::{Image, RenderTarget};
+use spacer::integrator::PathIntegrator;
+use spacer::light::{SkyLight, SkyParams};
+use spacer::material::Material;
+use spacer::math::{Transform, Vec3, vec3};
+use spacer::primitives::{HittableList, Sphere};
+use spacer::renderer::{MtRenderer, RenderHandle, Renderer, StRenderer, StopConditions};
+use spacer::scene::Scene;
+
+const TILE_SIZE: u32 = 8;
+const WIDTH: u32 = 45;
+const HEIGHT: u32 = 30;
+
+/// Keeps the exact float colors, which differ long before the 8-bit pixels do.
+struct FloatImage {
+    pixels: Vec<[u32; 3]>,
+}
+
+impl RenderTarget for FloatImage {
+    fn get_width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn get_height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn coordinate(&self, x: u32, y: u32) -> (u32, u32) {
+        (x, y)
+    }
+
+    fn put_pixel(&mut self, x: u32, y: u32, color: Color) {
+        self.pixels[(y * WIDTH + x) as usize] = [
+            color.r().to_bits(),
+            color.g().to_bits(),
+            color.b().to_bits(),
+        ];
+    }
+}
+
+fn scene() -> Scene {
+    let mut world = HittableList::default();
+    world.add(Arc::new(Sphere {
+        center: vec3(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Material::lambertian(Color::new(0.5, 0.5, 0.5))),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(-1.2, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::dielectric(1.5)),
+    }));
+    world.add(Arc::new(Sphere {
+        center: vec3(1.2, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::metalic(Color::new(0.7, 0.6, 0.5), 0.2)),
+    }));
+
+    let mut scene = Scene::new(Arc::new(world));
+    let sky = SkyLight::new(SkyParams::default());
+    scene.add_light(Arc::new(sky.sun()));
+    scene.add_light(Arc::new(sky));
+    scene
+}
+
+fn camera() -> Camera {
+    let mut camera = Camera::new(CameraParams {
+        image_width: WIDTH,
+        image_height: HEIGHT,
+        fov: f32::to_radians(40.0),
+        samples_per_pixel: 4,
+        focus_dist: 8.0,
+        defocus_angle: f32::to_radians(0.5),
+        ..CameraParams::default()
+    });
+    camera.transform = Transform::look_at(vec3(0.0, 2.0, 8.0), Vec3::Y, Vec3::Y);
+    // Samples are splatted across the tile edges
+    camera.filter = Filter::mitchell(2.0);
+    camera.seed = 42;
+    camera
+}
+
+fn render(renderer: &impl Renderer) -> Image {
+    let mut image = Image::new(WIDTH, HEIGHT);
+    let scene = scene();
+    let integrator = PathIntegrator::new(8);
+    renderer.render(&camera(), &mut image, |ray, sampler| {
+        integrator.radiance(&scene, ray, sampler)
+    });
+    image
+}
+
+fn render_progressive(renderer: &impl Renderer) -> FloatImage {
+    let mut image = FloatImage {
+        pixels: vec![[0; 3]; (WIDTH * HEIGHT) as usize],
+    };
+    let scene = scene();
+    let integrator = PathIntegrator::new(8);
+    renderer.render_progressive(
+        &camera(),
+        &mut image,
+        |ray, sampler| integrator.radiance(&scene, ray, sampler),
+        StopConditions::passes(4),
+        &RenderHandle::new(),
+        |_, _| {},
+    );
+    image
+}
+
+#[test]
+fn images_are_identical_for_any_renderer_and_worker_count() {
+    let st = render(&StRenderer {
+        tile_size: TILE_SIZE,
+    });
+    for n_workers in [1, 8] {
+        let mut renderer = MtRenderer::new(n_workers);
+        renderer.tile_size = TILE_SIZE;
+        let mt = render(&renderer);
+        assert!(
+            st.pixels() == mt.pixels(),
+            "image of {n_workers} workers differs"
+        );
+    }
+}
+
+#[test]
+fn progressive_colors_are_identical_for_any_renderer_and_worker_count() {
+    let st = render_progressive(&StRenderer {
+        tile_size: TILE_SIZE,
+    });
+    for n_workers in [1, 8] {
+        let mut renderer = MtRenderer::new(n_workers);
+        renderer.tile_size = TILE_SIZE;
+        let mt = render_progressive(&renderer);
+        assert!(
+            st.pixels == mt.pixels,
+            "colors of {n_workers} workers differ"
+        );
+    }
+}